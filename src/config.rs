@@ -0,0 +1,309 @@
+use std::{fs::File, io::BufReader, ops::RangeInclusive, path::Path, time::Duration};
+
+use serde::Deserialize;
+
+use crate::ga::{
+    biased_fitness::BiasedFitness,
+    diversity::DiversityManagement,
+    initializer::{
+        DefaultInitializer, I1Seed, MixedInitializer, PopulationInitializer, SavingsInitializer,
+        SeededInitializer, SolomonI1Initializer, SweepInitializer,
+    },
+    island::{MigrantReplacement, MigrantSelection, Topology},
+    niching::{Clearing, FitnessSharing, Niching},
+    problem::InstanceDescription,
+    steady_state::SteadyState,
+    termination::{
+        AllOf, AnyOf, MaxEvaluations, MaxGenerations, PopulationConvergence, Stagnation,
+        TargetObjective, TerminationCriterion, TimeLimit,
+    },
+};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RunConfig {
+    pub instance: String,
+    pub population: usize,
+    // Children per generation, the population size by default
+    pub offspring: Option<usize>,
+    pub steady_state: Option<SteadyState>,
+    pub diversity: Option<DiversityManagement>,
+    pub biased_fitness: Option<BiasedFitness>,
+    pub niching: Option<NichingConfig>,
+    pub initializers: Vec<InitializerShare>,
+    #[serde(default = "default_initializer_attempts")]
+    pub initializer_attempts: usize,
+    pub termination: TerminationConfig,
+    // Where to write the statistics of each run, {run} is replaced by the run number
+    pub statistics_output: Option<String>,
+    #[serde(default = "default_runs")]
+    pub runs: usize,
+    pub seed: Option<u64>,
+    pub checkpoint: Option<CheckpointConfig>,
+    // How many evaluated genomes each island remembers, 0 turns the cache off
+    #[serde(default = "default_evaluation_cache")]
+    pub evaluation_cache: usize,
+    // Every run is one island, the islands exchange migrants while they evolve
+    pub islands: IslandConfig,
+}
+
+fn default_runs() -> usize {
+    6
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CheckpointConfig {
+    pub directory: String,
+    pub interval: usize,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct IslandConfig {
+    pub migration_interval: usize,
+    pub migrants: usize,
+    pub topology: Topology,
+    pub migrant_selection: MigrantSelection,
+    pub replacement: MigrantReplacement,
+    // Operator settings per island, reused from the start when there are more islands
+    #[serde(default)]
+    pub operators: Vec<OperatorConfig>,
+}
+
+impl IslandConfig {
+    pub fn operators(&self, island: usize) -> OperatorConfig {
+        if self.operators.is_empty() {
+            OperatorConfig::default()
+        } else {
+            self.operators[island % self.operators.len()]
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct OperatorConfig {
+    pub crossover_rate: f64,
+    pub swap_rate: f64,
+    pub insert_rate: f64,
+}
+
+impl Default for OperatorConfig {
+    fn default() -> Self {
+        OperatorConfig {
+            crossover_rate: 0.3,
+            swap_rate: 0.0075,
+            insert_rate: 0.01,
+        }
+    }
+}
+
+fn default_evaluation_cache() -> usize {
+    5000
+}
+
+fn default_initializer_attempts() -> usize {
+    3
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct InitializerShare {
+    pub proportion: f64,
+    #[serde(flatten)]
+    pub initializer: InitializerConfig,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum InitializerConfig {
+    Random,
+    SolomonI1 {
+        alpha: (f64, f64),
+        mu: (f64, f64),
+        lambda: (f64, f64),
+    },
+    Savings {
+        perturbation: f64,
+    },
+    Sweep,
+    Seeded {
+        files: Vec<String>,
+        perturbed_proportion: f64,
+        perturbation_moves: usize,
+    },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum TerminationConfig {
+    MaxGenerations { generations: usize },
+    TimeLimit { seconds: f64 },
+    MaxEvaluations { evaluations: usize },
+    Stagnation { generations: usize },
+    TargetObjective { objective: f64 },
+    PopulationConvergence { tolerance: f64 },
+    AnyOf { criteria: Vec<TerminationConfig> },
+    AllOf { criteria: Vec<TerminationConfig> },
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type")]
+pub enum NichingConfig {
+    FitnessSharing { radius: f64, alpha: f64 },
+    Clearing { radius: f64, capacity: usize },
+}
+
+fn range((start, end): (f64, f64)) -> RangeInclusive<f64> {
+    start..=end
+}
+
+impl RunConfig {
+    pub fn from_file<P>(path: P) -> RunConfig
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path).unwrap();
+        let config: RunConfig = serde_json::from_reader(BufReader::new(file)).unwrap();
+        config.validate();
+        config
+    }
+
+    // Rejects settings the GA cannot run with
+    fn validate(&self) {
+        // Crossover pairs the parents
+        assert!(
+            self.offspring
+                .is_none_or(|offspring| offspring > 0 && offspring.is_multiple_of(2)),
+            "offspring must be a positive even number"
+        );
+        assert!(
+            self.steady_state.is_none() || self.offspring.is_none(),
+            "offspring cannot be set for a steady state GA, which breeds a population worth of \
+            children each generation"
+        );
+        assert!(
+            self.islands.migration_interval > 0,
+            "migration_interval must be at least 1"
+        );
+        assert!(
+            self.checkpoint
+                .as_ref()
+                .is_none_or(|checkpoint| checkpoint.interval > 0),
+            "checkpoint interval must be at least 1"
+        );
+        match self.niching {
+            // A radius of 0 leaves every niche empty, and the evals are divided by that
+            Some(NichingConfig::FitnessSharing { radius, alpha }) => assert!(
+                radius > 0. && alpha > 0.,
+                "fitness sharing needs a positive radius and alpha"
+            ),
+            Some(NichingConfig::Clearing { radius, .. }) => {
+                assert!(radius > 0., "clearing needs a positive radius")
+            }
+            None => {}
+        }
+    }
+
+    pub fn build_initializer(&self, instance: &InstanceDescription) -> MixedInitializer {
+        let initializers = self
+            .initializers
+            .iter()
+            .map(|share| {
+                (
+                    share.proportion,
+                    share.initializer.build(instance, self.population),
+                )
+            })
+            .collect();
+        MixedInitializer {
+            initial_population: self.population,
+            initializers,
+            max_attempts: self.initializer_attempts,
+            patient_amount: instance.patients.len(),
+            nurses: instance.nbr_nurses,
+            report: true,
+        }
+    }
+}
+
+impl InitializerConfig {
+    pub fn build(
+        &self,
+        instance: &InstanceDescription,
+        population: usize,
+    ) -> Box<dyn PopulationInitializer> {
+        match self {
+            InitializerConfig::Random => Box::new(DefaultInitializer {
+                initial_population: population,
+                patient_amount: instance.patients.len(),
+                nurses: instance.nbr_nurses,
+            }),
+            InitializerConfig::SolomonI1 { alpha, mu, lambda } => Box::new(SolomonI1Initializer {
+                initial_population: population,
+                problem_instance: instance.clone(),
+                alpha_range: range(*alpha),
+                mu_range: range(*mu),
+                lambda_range: range(*lambda),
+                seed_criteria: vec![I1Seed::FarthestPatient, I1Seed::EarliestDeadline],
+            }),
+            InitializerConfig::Savings { perturbation } => Box::new(SavingsInitializer {
+                initial_population: population,
+                problem_instance: instance.clone(),
+                savings_perturbation: *perturbation,
+            }),
+            InitializerConfig::Sweep => Box::new(SweepInitializer {
+                initial_population: population,
+                problem_instance: instance.clone(),
+            }),
+            InitializerConfig::Seeded {
+                files,
+                perturbed_proportion,
+                perturbation_moves,
+            } => Box::new(SeededInitializer::from_files(
+                files,
+                instance,
+                population,
+                *perturbed_proportion,
+                *perturbation_moves,
+            )),
+        }
+    }
+}
+
+impl TerminationConfig {
+    pub fn build(&self) -> Box<dyn TerminationCriterion> {
+        match self {
+            TerminationConfig::MaxGenerations { generations } => {
+                Box::new(MaxGenerations(*generations))
+            }
+            TerminationConfig::TimeLimit { seconds } => {
+                Box::new(TimeLimit(Duration::from_secs_f64(*seconds)))
+            }
+            TerminationConfig::MaxEvaluations { evaluations } => {
+                Box::new(MaxEvaluations(*evaluations))
+            }
+            TerminationConfig::Stagnation { generations } => Box::new(Stagnation(*generations)),
+            TerminationConfig::TargetObjective { objective } => {
+                Box::new(TargetObjective(*objective))
+            }
+            TerminationConfig::PopulationConvergence { tolerance } => {
+                Box::new(PopulationConvergence(*tolerance))
+            }
+            TerminationConfig::AnyOf { criteria } => Box::new(AnyOf(
+                criteria.iter().map(TerminationConfig::build).collect(),
+            )),
+            TerminationConfig::AllOf { criteria } => Box::new(AllOf(
+                criteria.iter().map(TerminationConfig::build).collect(),
+            )),
+        }
+    }
+}
+
+impl NichingConfig {
+    pub fn build(&self) -> Box<dyn Niching> {
+        match *self {
+            NichingConfig::FitnessSharing { radius, alpha } => {
+                Box::new(FitnessSharing { radius, alpha })
+            }
+            NichingConfig::Clearing { radius, capacity } => Box::new(Clearing { radius, capacity }),
+        }
+    }
+}
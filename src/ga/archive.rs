@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+use super::problem::ProblemSolution;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ArchivedSolution<G = ProblemSolution> {
+    pub solution: G,
+    pub fitness: f64,
+    pub penalty: f64,
+    pub generation: usize,
+}
+
+// Keeps the best distinct solutions seen during a run, so they survive even if the survivor
+// selector throws them away. The best feasible solution is kept separately since it may rank
+// below infeasible ones.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HallOfFame<G = ProblemSolution> {
+    pub capacity: usize,
+    pub best: Vec<ArchivedSolution<G>>,
+    pub best_feasible: Option<ArchivedSolution<G>>,
+}
+
+impl<G: Clone + PartialEq> HallOfFame<G> {
+    // The engine reports the best solution every generation, so at least that one is kept
+    pub fn new(capacity: usize) -> HallOfFame<G> {
+        assert!(
+            capacity > 0,
+            "the hall of fame must keep at least one solution"
+        );
+        HallOfFame {
+            capacity,
+            best: Vec::with_capacity(capacity + 1),
+            best_feasible: None,
+        }
+    }
+
+    // Returns whether the best solution improved
+    pub fn update(
+        &mut self,
+        population: &[G],
+        population_eval: &[f64],
+        population_penalties: &[f64],
+        generation: usize,
+    ) -> bool {
+        let previous_best = self.best_fitness();
+        for ((solution, &fitness), &penalty) in population
+            .iter()
+            .zip(population_eval)
+            .zip(population_penalties)
+        {
+            if penalty == 0.
+                && self
+                    .best_feasible
+                    .as_ref()
+                    .is_none_or(|best| fitness > best.fitness)
+            {
+                self.best_feasible = Some(ArchivedSolution {
+                    solution: solution.clone(),
+                    fitness,
+                    penalty,
+                    generation,
+                });
+            }
+
+            let full = self.best.len() >= self.capacity;
+            if full
+                && self
+                    .best
+                    .last()
+                    .is_none_or(|worst| fitness <= worst.fitness)
+            {
+                continue;
+            }
+            if self
+                .best
+                .iter()
+                .any(|archived| &archived.solution == solution)
+            {
+                continue;
+            }
+            let position = self
+                .best
+                .iter()
+                .position(|archived| fitness > archived.fitness)
+                .unwrap_or(self.best.len());
+            self.best.insert(
+                position,
+                ArchivedSolution {
+                    solution: solution.clone(),
+                    fitness,
+                    penalty,
+                    generation,
+                },
+            );
+            self.best.truncate(self.capacity);
+        }
+        self.best_fitness() > previous_best
+    }
+
+    pub fn best(&self) -> Option<&ArchivedSolution<G>> {
+        self.best.first()
+    }
+
+    pub fn best_fitness(&self) -> f64 {
+        self.best().map_or(f64::NEG_INFINITY, |best| best.fitness)
+    }
+}
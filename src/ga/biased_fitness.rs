@@ -0,0 +1,73 @@
+use ordered_float::NotNan;
+use serde::Deserialize;
+
+// Ranks individuals by objective and by how different they are from their nearest neighbours, as
+// the biased fitness in Vidal's hybrid genetic search. Selectors get this instead of the eval, so
+// an individual that is close to others has to be clearly better to be picked.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct BiasedFitness {
+    // How many of the closest individuals the diversity contribution averages over
+    #[serde(default = "default_neighbours")]
+    pub neighbours: usize,
+    // The diversity rank is weighted by 1 - elite / population, so about this many of the best
+    // individuals keep their place whatever their diversity
+    #[serde(default = "default_elite")]
+    pub elite: usize,
+}
+
+fn default_neighbours() -> usize {
+    5
+}
+
+fn default_elite() -> usize {
+    4
+}
+
+impl BiasedFitness {
+    // Average distance from each individual to its nearest neighbours, given the distance matrix
+    // of the population
+    pub fn diversity_contribution(&self, distances: &[Vec<f64>]) -> Vec<f64> {
+        distances
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let mut row = row.clone();
+                row.swap_remove(i);
+                row.sort_unstable_by_key(|&distance| NotNan::new(distance).unwrap());
+                let closest = &row[..self.neighbours.min(row.len())];
+                if closest.is_empty() {
+                    0.
+                } else {
+                    closest.iter().sum::<f64>() / closest.len() as f64
+                }
+            })
+            .collect()
+    }
+
+    // The biased fitness of each individual, negated so that higher is better like the eval
+    pub fn evaluate(&self, population_eval: &[f64], distances: &[Vec<f64>]) -> Vec<f64> {
+        let n = population_eval.len();
+        if n < 2 {
+            return vec![0.; n];
+        }
+        let contribution = self.diversity_contribution(distances);
+        // Rank 0 is the best, normalized to [0, 1]
+        let normalized_rank = |key: &dyn Fn(usize) -> NotNan<f64>| {
+            let mut order: Vec<usize> = (0..n).collect();
+            order.sort_by_key(|&i| std::cmp::Reverse(key(i)));
+            let mut rank = vec![0.; n];
+            for (position, i) in order.into_iter().enumerate() {
+                rank[i] = position as f64 / (n - 1) as f64;
+            }
+            rank
+        };
+        let cost_rank = normalized_rank(&|i| NotNan::new(population_eval[i]).unwrap());
+        let diversity_rank = normalized_rank(&|i| NotNan::new(contribution[i]).unwrap());
+        let diversity_weight = 1. - self.elite.min(n) as f64 / n as f64;
+        cost_rank
+            .iter()
+            .zip(&diversity_rank)
+            .map(|(cost, diversity)| -(cost + diversity_weight * diversity))
+            .collect()
+    }
+}
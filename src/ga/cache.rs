@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::problem::ProblemSolution;
+use super::Evaluation;
+
+// Remembers the evaluation of recently seen genomes, so children that are copies of earlier
+// individuals are not evaluated again. Entries go into the recent map, and when that fills up it
+// replaces the older one. Anything looked up or refreshed since then is moved back into the recent
+// map, so the current population is kept while stale genomes are dropped.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound(
+    serialize = "G: Serialize",
+    deserialize = "G: DeserializeOwned + Eq + Hash"
+))]
+pub struct EvaluationCache<G = ProblemSolution> {
+    pub capacity: usize,
+    #[serde(with = "entries")]
+    recent: HashMap<G, Evaluation>,
+    #[serde(with = "entries")]
+    older: HashMap<G, Evaluation>,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl<G: Eq + Hash> EvaluationCache<G> {
+    // A capacity of 0 turns the cache off
+    pub fn new(capacity: usize) -> EvaluationCache<G> {
+        EvaluationCache {
+            capacity,
+            recent: HashMap::new(),
+            older: HashMap::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn get(&mut self, solution: &G) -> Option<Evaluation> {
+        if let Some(evaluation) = self.recent.get(solution) {
+            self.hits += 1;
+            return Some(evaluation.clone());
+        }
+        if let Some((solution, evaluation)) = self.older.remove_entry(solution) {
+            self.hits += 1;
+            self.insert(solution, evaluation.clone());
+            return Some(evaluation);
+        }
+        self.misses += 1;
+        None
+    }
+
+    // Keeps the evaluations of genomes the engine already has in the recent map without counting
+    // a lookup, so the current population is not dropped
+    pub fn refresh(&mut self, solutions: &[G]) {
+        for solution in solutions {
+            if let Some((solution, evaluation)) = self.older.remove_entry(solution) {
+                self.insert(solution, evaluation);
+            }
+        }
+    }
+
+    pub fn insert(&mut self, solution: G, evaluation: Evaluation) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.recent.len() >= self.capacity {
+            self.older = mem::take(&mut self.recent);
+        }
+        self.recent.insert(solution, evaluation);
+    }
+
+    // Share of lookups since the last call that were answered from the cache
+    pub fn take_hit_rate(&mut self) -> f64 {
+        let lookups = self.hits + self.misses;
+        let rate = if lookups == 0 {
+            0.
+        } else {
+            self.hits as f64 / lookups as f64
+        };
+        self.hits = 0;
+        self.misses = 0;
+        rate
+    }
+}
+
+// Genomes are not strings, so the maps are written as lists of entries
+mod entries {
+    use super::*;
+
+    pub fn serialize<G: Serialize, S: Serializer>(
+        map: &HashMap<G, Evaluation>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(map)
+    }
+
+    pub fn deserialize<'de, G, D>(deserializer: D) -> Result<HashMap<G, Evaluation>, D::Error>
+    where
+        G: DeserializeOwned + Eq + Hash,
+        D: Deserializer<'de>,
+    {
+        let entries: Vec<(G, Evaluation)> = Vec::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
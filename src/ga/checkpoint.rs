@@ -0,0 +1,34 @@
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::archive::HallOfFame;
+use super::cache::EvaluationCache;
+use super::individual::Metadata;
+use super::problem::ProblemSolution;
+use super::statistics::StatisticsHistory;
+use super::Genome;
+
+// Everything the engine needs to continue a run, taken between generations once the current one
+// has been evaluated and recorded. The island model saves these for all islands together.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "G: Genome"))]
+pub struct GAState<G = ProblemSolution> {
+    pub generation: usize,
+    // An island that met its termination criterion stays finished while the others go on
+    pub finished: bool,
+    pub elapsed_seconds: f64,
+    pub evaluations: usize,
+    pub generations_without_improvement: usize,
+    pub population: Vec<G>,
+    pub population_eval: Vec<f64>,
+    pub population_penalties: Vec<f64>,
+    pub population_violations: Vec<Vec<f64>>,
+    pub rng: ChaCha8Rng,
+    pub statistics: StatisticsHistory,
+    pub hall_of_fame: HallOfFame<G>,
+    pub population_metadata: Vec<Metadata>,
+    pub last_restart: usize,
+    pub next_id: usize,
+    // Keeps the evaluation count and cache hit rate of a resumed run the same as an uninterrupted one
+    pub cache: EvaluationCache<G>,
+}
@@ -1,14 +1,15 @@
 use std::{collections::HashMap, hash::BuildHasher};
 
 use super::problem::{ProblemSolution, SolutionField};
+use super::random;
 use rand::{
     distributions::{Distribution, Uniform},
     Rng,
 };
 use serde::__private::de::ContentDeserializer;
 
-pub trait ParentCrossoverSystem {
-    fn cross_over(&self, parents: &[ProblemSolution]) -> Vec<ProblemSolution>;
+pub trait ParentCrossoverSystem<G = ProblemSolution>: Sync {
+    fn cross_over(&self, parents: &[G]) -> Vec<G>;
 }
 
 pub struct DefaultCrossover {
@@ -20,7 +21,7 @@ impl ParentCrossoverSystem for DefaultCrossover {
         let mut crossed_overs: Vec<ProblemSolution> = Vec::new();
 
         let dist = Uniform::from(1..parents[0].0.len() - 1);
-        let mut rng = rand::thread_rng();
+        let mut rng = random::rng();
 
         for pair in parents.chunks(2) {
             if !rng.gen_bool(self.crossover_rate) {
@@ -99,7 +100,7 @@ pub struct CycleCrossover {
 
 impl ParentCrossoverSystem for CycleCrossover {
     fn cross_over(&self, parents: &[ProblemSolution]) -> Vec<ProblemSolution> {
-        let mut rng = rand::thread_rng();
+        let mut rng = random::rng();
         let mut children: Vec<ProblemSolution> = Vec::with_capacity(parents.len());
         for parents in parents.chunks(2) {
             let p_1 = &parents[0];
@@ -121,7 +122,7 @@ pub struct EdgeCrossover {
 
 impl ParentCrossoverSystem for EdgeCrossover {
     fn cross_over(&self, parents: &[ProblemSolution]) -> Vec<ProblemSolution> {
-        let mut rng = rand::thread_rng();
+        let mut rng = random::rng();
         let mut children: Vec<ProblemSolution> = Vec::with_capacity(parents.len());
         for parents in parents.chunks(2) {
             let p_1 = &parents[0];
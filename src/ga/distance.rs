@@ -0,0 +1,303 @@
+use rayon::prelude::*;
+
+use super::problem::{ProblemSolution, SolutionField};
+use super::Genome;
+
+// How different two genomes are, 0 for genomes that represent the same solution
+pub trait DistanceMetric<G = ProblemSolution>: Sync {
+    fn distance(&self, a: &G, b: &G) -> f64;
+
+    // Distances of all pairs i < j, ordered (0, 1), (0, 2), .., (1, 2), ... Metrics that can
+    // prepare each genome once override this.
+    fn pairwise(&self, population: &[&G]) -> Vec<f64>
+    where
+        G: Sync,
+    {
+        pairs(population.len())
+            .par_iter()
+            .map(|&(i, j)| self.distance(population[i], population[j]))
+            .collect()
+    }
+}
+
+// The index pairs in the order pairwise returns them
+pub fn pairs(n: usize) -> Vec<(usize, usize)> {
+    (0..n)
+        .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+        .collect()
+}
+
+// Distances between all individuals, row i holds the distance from i to every individual
+pub fn distance_matrix<G: Sync>(
+    metric: &dyn DistanceMetric<G>,
+    population: &[&G],
+) -> Vec<Vec<f64>> {
+    let n = population.len();
+    let mut matrix = vec![vec![0.; n]; n];
+    for ((i, j), distance) in pairs(n).into_iter().zip(metric.pairwise(population)) {
+        matrix[i][j] = distance;
+        matrix[j][i] = distance;
+    }
+    matrix
+}
+
+// Brings the distance matrix up to date after individual i of the population was replaced
+pub fn update_distance_matrix<G: Sync>(
+    matrix: &mut [Vec<f64>],
+    metric: &dyn DistanceMetric<G>,
+    population: &[G],
+    i: usize,
+) {
+    let row: Vec<f64> = population
+        .par_iter()
+        .enumerate()
+        .map(|(j, other)| {
+            if i == j {
+                0.
+            } else {
+                metric.distance(&population[i], other)
+            }
+        })
+        .collect();
+    for (j, &distance) in row.iter().enumerate() {
+        matrix[j][i] = distance;
+    }
+    matrix[i] = row;
+}
+
+// The distance the genome type itself defines
+pub struct GenomeDistance;
+
+impl<G: Genome> DistanceMetric<G> for GenomeDistance {
+    fn distance(&self, a: &G, b: &G) -> f64 {
+        a.distance(b)
+    }
+}
+
+// Number of positions holding different tokens
+pub struct Hamming;
+
+impl DistanceMetric for Hamming {
+    fn distance(&self, a: &ProblemSolution, b: &ProblemSolution) -> f64 {
+        a.0.iter().zip(&b.0).filter(|(a, b)| a != b).count() as f64
+    }
+}
+
+fn routes(solution: &ProblemSolution) -> Vec<Vec<usize>> {
+    let mut routes = vec![Vec::new()];
+    for token in &solution.0 {
+        match token {
+            SolutionField::Patient(id) => routes.last_mut().unwrap().push(*id),
+            SolutionField::Separator(_) => routes.push(Vec::new()),
+        }
+    }
+    routes.retain(|route| !route.is_empty());
+    routes
+}
+
+// The routes of the solution in a fixed order, equal for solutions that only differ in which
+// nurse drives which route or where the empty routes are
+pub fn canonical_routes(solution: &ProblemSolution) -> Vec<Vec<usize>> {
+    let mut routes = routes(solution);
+    routes.sort_unstable();
+    routes
+}
+
+// The stop after each patient, None for the depot, and whether the patient starts a route
+struct Successors {
+    successor: Vec<Option<usize>>,
+    first: Vec<bool>,
+}
+
+impl Successors {
+    fn new(solution: &ProblemSolution, patients: usize) -> Successors {
+        let mut successor = vec![None; patients];
+        let mut first = vec![false; patients];
+        let mut previous = None;
+        for token in &solution.0 {
+            match *token {
+                SolutionField::Patient(id) => {
+                    match previous {
+                        Some(previous) => successor[previous] = Some(id),
+                        None => first[id] = true,
+                    }
+                    previous = Some(id);
+                }
+                SolutionField::Separator(_) => previous = None,
+            }
+        }
+        Successors { successor, first }
+    }
+
+    // Every patient is left by one arc in each solution, so a different successor is an arc
+    // missing from each side. An arc out of the depot is missing from one side.
+    fn broken_pairs(&self, other: &Successors) -> f64 {
+        let mut broken = 0;
+        for id in 0..self.successor.len() {
+            if self.successor[id] != other.successor[id] {
+                broken += 2;
+            }
+            if self.first[id] != other.first[id] {
+                broken += 1;
+            }
+        }
+        broken as f64 / 2.
+    }
+}
+
+fn patient_bound<'a>(solutions: impl IntoIterator<Item = &'a ProblemSolution>) -> usize {
+    solutions
+        .into_iter()
+        .flat_map(|solution| &solution.0)
+        .filter_map(|token| match token {
+            SolutionField::Patient(id) => Some(id + 1),
+            SolutionField::Separator(_) => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+// Number of arcs one solution drives and the other does not, averaged over both directions.
+// Routes are compared as sets of arcs, so the order of the nurses does not matter.
+pub struct BrokenPairs;
+
+impl DistanceMetric for BrokenPairs {
+    fn distance(&self, a: &ProblemSolution, b: &ProblemSolution) -> f64 {
+        let patients = patient_bound([a, b]);
+        Successors::new(a, patients).broken_pairs(&Successors::new(b, patients))
+    }
+
+    fn pairwise(&self, population: &[&ProblemSolution]) -> Vec<f64> {
+        let patients = patient_bound(population.iter().copied());
+        let successors: Vec<Successors> = population
+            .iter()
+            .map(|solution| Successors::new(solution, patients))
+            .collect();
+        pairs(population.len())
+            .par_iter()
+            .map(|&(i, j)| successors[i].broken_pairs(&successors[j]))
+            .collect()
+    }
+}
+
+// Number of pairs of patients that share a nurse in one solution but not in the other. Only
+// which patients are visited together matters, not by which nurse or in which order.
+pub struct RouteAssignment;
+
+impl DistanceMetric for RouteAssignment {
+    fn distance(&self, a: &ProblemSolution, b: &ProblemSolution) -> f64 {
+        let pairs = |size: usize| (size * size.saturating_sub(1) / 2) as f64;
+        let routes_a = routes(a);
+        let routes_b = routes(b);
+        let patients = routes_b.iter().flatten().max().map_or(0, |&id| id + 1);
+        let mut route_b = vec![0; patients];
+        for (index, route) in routes_b.iter().enumerate() {
+            for &id in route {
+                route_b[id] = index;
+            }
+        }
+        // Pairs together in both are pairs within one route of a and one route of b
+        let mut overlaps = vec![0; routes_b.len()];
+        let mut together_both = 0.;
+        for route in &routes_a {
+            for &id in route {
+                overlaps[route_b[id]] += 1;
+            }
+            for &id in route {
+                together_both += pairs(overlaps[route_b[id]]);
+                overlaps[route_b[id]] = 0;
+            }
+        }
+        let together_a: f64 = routes_a.iter().map(|route| pairs(route.len())).sum();
+        let together_b: f64 = routes_b.iter().map(|route| pairs(route.len())).sum();
+        together_a + together_b - 2. * together_both
+    }
+}
+
+// Number of insertions, deletions and substitutions that turn one solution into the other, with
+// all separators alike. The routes are put in a canonical order first so the order of the nurses
+// does not matter, only the routes themselves.
+pub struct EditDistance;
+
+fn canonical_tour(solution: &ProblemSolution) -> Vec<Option<usize>> {
+    canonical_routes(solution)
+        .into_iter()
+        .flat_map(|route| route.into_iter().map(Some).chain(std::iter::once(None)))
+        .collect()
+}
+
+impl DistanceMetric for EditDistance {
+    fn distance(&self, a: &ProblemSolution, b: &ProblemSolution) -> f64 {
+        let tour_a = canonical_tour(a);
+        let tour_b = canonical_tour(b);
+        // Wagner-Fischer keeping a single row
+        let mut row: Vec<usize> = (0..=tour_b.len()).collect();
+        for (i, stop_a) in tour_a.iter().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i + 1;
+            for (j, stop_b) in tour_b.iter().enumerate() {
+                let substitution = diagonal + usize::from(stop_a != stop_b);
+                diagonal = row[j + 1];
+                row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+            }
+        }
+        row[tour_b.len()] as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::{seq::SliceRandom, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    // Arcs driven in the solution, None is the depot. BrokenPairs counted these before it was
+    // rewritten to compare successors.
+    fn arcs(solution: &ProblemSolution) -> HashSet<(Option<usize>, Option<usize>)> {
+        let mut arcs = HashSet::new();
+        for route in routes(solution) {
+            let stops: Vec<Option<usize>> = std::iter::once(None)
+                .chain(route.into_iter().map(Some))
+                .chain(std::iter::once(None))
+                .collect();
+            arcs.extend(stops.windows(2).map(|arc| (arc[0], arc[1])));
+        }
+        arcs
+    }
+
+    fn arc_distance(a: &ProblemSolution, b: &ProblemSolution) -> f64 {
+        let arcs_a = arcs(a);
+        let arcs_b = arcs(b);
+        (arcs_a.difference(&arcs_b).count() + arcs_b.difference(&arcs_a).count()) as f64 / 2.
+    }
+
+    // Many separators for few patients, so there are empty routes at the start, the end and in
+    // between
+    fn random_solution(rng: &mut ChaCha8Rng) -> ProblemSolution {
+        let mut tokens: Vec<SolutionField> = (0..12)
+            .map(SolutionField::Patient)
+            .chain((0..6).map(SolutionField::Separator))
+            .collect();
+        tokens.shuffle(rng);
+        ProblemSolution(tokens)
+    }
+
+    #[test]
+    fn broken_pairs_counts_the_arcs_only_one_solution_drives() {
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let solutions: Vec<ProblemSolution> = (0..40).map(|_| random_solution(&mut rng)).collect();
+        let solutions: Vec<&ProblemSolution> = solutions.iter().collect();
+
+        let expected: Vec<f64> = pairs(solutions.len())
+            .into_iter()
+            .map(|(i, j)| arc_distance(solutions[i], solutions[j]))
+            .collect();
+        assert_eq!(BrokenPairs.pairwise(&solutions), expected);
+        for (&(i, j), &distance) in pairs(solutions.len()).iter().zip(&expected) {
+            assert_eq!(BrokenPairs.distance(solutions[i], solutions[j]), distance);
+        }
+    }
+}
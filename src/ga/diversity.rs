@@ -0,0 +1,118 @@
+use ordered_float::NotNan;
+use serde::Deserialize;
+
+use super::distance::distance_matrix;
+use super::individual::Origin;
+use super::{random, GARun, Genome};
+
+// Keeps the population from collapsing into copies of one solution. Duplicates are replaced by
+// new individuals, and when the population has converged or stopped improving all but the best
+// few are replaced. New individuals come from the population initializer. Individuals at distance
+// 0 under the GA's distance metric count as duplicates.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct DiversityManagement {
+    // Replace individuals identical to a better one in the population
+    #[serde(default)]
+    pub eliminate_duplicates: bool,
+    // Restart when the diversity statistic falls below this, which is measured with the GA's
+    // distance metric
+    pub min_diversity: Option<f64>,
+    // Restart after this many generations without a new best, counted from the last restart
+    pub max_stagnation: Option<usize>,
+    // How many of the best individuals survive a restart
+    #[serde(default = "default_elite")]
+    pub elite: usize,
+    // How often a new individual that duplicates one in the population is rebuilt before it is
+    // let in anyway
+    #[serde(default = "default_attempts")]
+    pub attempts: usize,
+}
+
+fn default_elite() -> usize {
+    10
+}
+
+fn default_attempts() -> usize {
+    3
+}
+
+impl<'a, G: Genome, P: Clone + Send + Sync> GARun<'a, G, P> {
+    // Runs on the evaluated survivors, before the generation is recorded
+    pub(super) fn manage_diversity(&mut self, diversity: DiversityManagement) {
+        let population_size = self.ga.population.len();
+        let eval = |i: usize| NotNan::new(self.ga.population_eval[i]).unwrap();
+        let mut ranked: Vec<usize> = (0..population_size).collect();
+        ranked.sort_by_key(|&i| std::cmp::Reverse(eval(i)));
+
+        let last_diversity = self.ga.statistics.0.last().map(|s| s.diversity);
+        let converged = diversity
+            .min_diversity
+            .is_some_and(|min| last_diversity.is_some_and(|last| last < min));
+        let stagnated = diversity.max_stagnation.is_some_and(|max| {
+            self.generations_without_improvement
+                .min(self.generation - self.last_restart)
+                >= max
+        });
+
+        let replace: Vec<usize> = if converged || stagnated {
+            self.last_restart = self.generation;
+            ranked.split_off(diversity.elite.min(population_size))
+        } else if diversity.eliminate_duplicates {
+            let individuals: Vec<&G> = self.ga.population.iter().collect();
+            let distances = distance_matrix(self.ga.distance_metric, &individuals);
+            let mut kept: Vec<usize> = Vec::with_capacity(population_size);
+            ranked
+                .into_iter()
+                .filter(|&i| {
+                    let duplicate = kept.iter().any(|&better| distances[i][better] == 0.);
+                    if !duplicate {
+                        kept.push(i);
+                    }
+                    duplicate
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if replace.is_empty() {
+            return;
+        }
+
+        let new_individuals = self.new_individuals(&replace, diversity.attempts);
+        let (evaluations, computed) = self.ga.evaluate_cached(&new_individuals);
+        self.evaluations += computed;
+        for ((&index, individual), evaluation) in
+            replace.iter().zip(new_individuals).zip(evaluations)
+        {
+            let metadata = self.new_metadata(Origin::Initializer, Vec::new());
+            self.put_individual(index, individual, evaluation, metadata);
+        }
+        self.ga.statistics.0.last_mut().unwrap().reinitialized = replace.len();
+    }
+
+    // Individuals to take the place of those at the given indices, which do not duplicate the
+    // rest of the population or each other
+    fn new_individuals(&mut self, replace: &[usize], attempts: usize) -> Vec<G> {
+        let ga = &mut self.ga;
+        let mut new_individuals: Vec<G> = Vec::with_capacity(replace.len());
+        let mut attempt = 0;
+        while new_individuals.len() < replace.len() {
+            let missing = replace.len() - new_individuals.len();
+            let candidates = random::scoped(&mut ga.rng, || {
+                ga.population_initializer.initialize_individuals(missing)
+            });
+            attempt += 1;
+            for candidate in candidates {
+                let duplicate = (0..ga.population.len())
+                    .filter(|i| !replace.contains(i))
+                    .map(|i| &ga.population[i])
+                    .chain(&new_individuals)
+                    .any(|other| ga.distance_metric.distance(&candidate, other) == 0.);
+                if !duplicate || attempt > attempts {
+                    new_individuals.push(candidate);
+                }
+            }
+        }
+        new_individuals
+    }
+}
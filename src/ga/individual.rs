@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{GARun, Genome};
+
+// The operator an individual came from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Initializer,
+    Crossover,
+    Mutation,
+    Repair,
+    // An unchanged copy of its parent, which keeps the parent's birth
+    Copy,
+    Migration,
+}
+
+// What the engine keeps track of for each individual besides its genome
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Metadata {
+    // Unique within one run of the GA
+    pub id: usize,
+    // The generation the individual joined the population in
+    pub birth: usize,
+    pub origin: Origin,
+    pub parents: Vec<usize>,
+}
+
+impl Metadata {
+    pub fn age(&self, generation: usize) -> usize {
+        generation.saturating_sub(self.birth)
+    }
+}
+
+// Finds each survivor among the parents and children it was selected from, as an index into the
+// parents followed by the children. Survivors with the same genome are matched with its copies in
+// turn, parents first.
+pub fn survivor_sources<G: Genome>(survivors: &[G], parents: &[G], children: &[G]) -> Vec<usize> {
+    let mut copies: HashMap<&G, Vec<usize>> = HashMap::new();
+    let children = children
+        .iter()
+        .enumerate()
+        .map(|(i, genome)| (parents.len() + i, genome));
+    for (source, genome) in children.chain(parents.iter().enumerate()) {
+        copies.entry(genome).or_default().push(source);
+    }
+    survivors
+        .iter()
+        .map(|survivor| {
+            let copies = copies
+                .get_mut(survivor)
+                .expect("survivors are parents or children");
+            // The last copy goes to any further survivors with the same genome
+            if copies.len() > 1 {
+                copies.pop().unwrap()
+            } else {
+                copies[0]
+            }
+        })
+        .collect()
+}
+
+// Takes whatever the engine keeps per individual for the survivors, so their metadata and
+// evaluations carry over from the parents and children they were selected from
+pub fn for_survivors<T: Clone>(sources: &[usize], parents: &[T], children: &[T]) -> Vec<T> {
+    sources
+        .iter()
+        .map(|&source| match parents.get(source) {
+            Some(parent) => parent.clone(),
+            None => children[source - parents.len()].clone(),
+        })
+        .collect()
+}
+
+// Counts the children with a better eval than every parent they were bred from
+pub fn count_improved(
+    children_eval: &[f64],
+    children_metadata: &[Metadata],
+    parent_eval: &[f64],
+    parent_metadata: &[Metadata],
+) -> usize {
+    let eval: HashMap<usize, f64> = parent_metadata
+        .iter()
+        .map(|metadata| metadata.id)
+        .zip(parent_eval.iter().copied())
+        .collect();
+    children_eval
+        .iter()
+        .zip(children_metadata)
+        .filter(|(&child, metadata)| {
+            let best_parent = metadata
+                .parents
+                .iter()
+                .filter_map(|id| eval.get(id).copied())
+                .reduce(f64::max);
+            best_parent.is_some_and(|parent| child > parent)
+        })
+        .count()
+}
+
+impl<'a, G: Genome, P: Clone + Send + Sync> GARun<'a, G, P> {
+    pub(super) fn new_metadata(&mut self, origin: Origin, parents: Vec<usize>) -> Metadata {
+        self.next_id += 1;
+        Metadata {
+            id: self.next_id - 1,
+            birth: self.generation,
+            origin,
+            parents,
+        }
+    }
+
+    // Metadata of children bred from the selected parents, which are taken pairwise by crossover.
+    // Children are born in the next generation.
+    pub(super) fn children_metadata(
+        &mut self,
+        selected_parents: &[G],
+        crossed_over: &[G],
+        mutated: &[G],
+        repaired: &[G],
+    ) -> Vec<Metadata> {
+        let population_metadata: HashMap<&G, &Metadata> = self
+            .ga
+            .population
+            .iter()
+            .zip(&self.ga.population_metadata)
+            .collect();
+        let parent_metadata: Vec<Option<Metadata>> = selected_parents
+            .iter()
+            .map(|parent| population_metadata.get(parent).map(|&m| m.clone()))
+            .collect();
+        let id = |i: usize| parent_metadata[i].as_ref().map(|m| m.id);
+
+        let mut children = Vec::with_capacity(repaired.len());
+        for (i, child) in repaired.iter().enumerate() {
+            let start = i - i % 2;
+            let pair = start..(start + 2).min(selected_parents.len());
+            // Crossover may hand back either parent unchanged
+            let source = pair
+                .clone()
+                .find(|&parent| selected_parents[parent] == crossed_over[i]);
+            let (origin, parents, birth) = match source {
+                None => (Origin::Crossover, pair.filter_map(id).collect(), None),
+                Some(source) => {
+                    let origin = if mutated[i] != crossed_over[i] {
+                        Origin::Mutation
+                    } else if *child != mutated[i] {
+                        Origin::Repair
+                    } else {
+                        Origin::Copy
+                    };
+                    let birth = parent_metadata[source]
+                        .as_ref()
+                        .filter(|_| origin == Origin::Copy)
+                        .map(|parent| parent.birth);
+                    (origin, id(source).into_iter().collect(), birth)
+                }
+            };
+            let mut metadata = self.new_metadata(origin, parents);
+            metadata.birth = birth.unwrap_or(self.generation + 1);
+            children.push(metadata);
+        }
+        children
+    }
+}
@@ -1,10 +1,25 @@
+use std::collections::HashSet;
+use std::f64::consts::TAU;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use ordered_float::NotNan;
 use rand::distributions::{Distribution, Uniform};
 use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::distance::{canonical_routes, BrokenPairs, DistanceMetric};
+use super::problem::{InstanceDescription, Phenotype, ProblemSolution, RouteSchedule};
+use super::random;
+
+pub trait PopulationInitializer<G = ProblemSolution>: Sync {
+    fn population_size(&self) -> usize;
 
-use super::problem::{Phenotype, ProblemSolution};
+    fn initialize_individuals(&self, amount: usize) -> Vec<G>;
 
-pub trait PopulationInitializer {
-    fn initialize_population(&self) -> Vec<ProblemSolution>;
+    fn initialize_population(&self) -> Vec<G> {
+        self.initialize_individuals(self.population_size())
+    }
 }
 
 pub struct DefaultInitializer {
@@ -14,11 +29,15 @@ pub struct DefaultInitializer {
 }
 
 impl PopulationInitializer for DefaultInitializer {
-    fn initialize_population(&self) -> Vec<ProblemSolution> {
+    fn population_size(&self) -> usize {
+        self.initial_population
+    }
+
+    fn initialize_individuals(&self, amount: usize) -> Vec<ProblemSolution> {
         let mut population: Vec<Phenotype> = Vec::new();
-        let mut rng = rand::thread_rng();
+        let mut rng = random::rng();
 
-        for _ in 0..self.initial_population {
+        for _ in 0..amount {
             let mut nurses: Vec<Vec<usize>> = Vec::new();
             for _ in 0..self.nurses {
                 nurses.push(Vec::new())
@@ -43,3 +62,576 @@ impl PopulationInitializer for DefaultInitializer {
         solutions
     }
 }
+
+// Inserts a patient where it adds the least travel time, preferring positions that keep the route
+// feasible
+fn insert_cheapest(instance: &InstanceDescription, routes: &mut [Vec<usize>], patient: usize) {
+    let mut best: Option<(bool, usize, usize, f64)> = None;
+    let mut candidate = Vec::new();
+    for (route_index, route) in routes.iter().enumerate() {
+        for position in 0..=route.len() {
+            let previous = position.checked_sub(1).map(|i| route[i]);
+            let next = route.get(position).copied();
+            let cost = instance.travel_time(previous, Some(patient))
+                + instance.travel_time(Some(patient), next)
+                - instance.travel_time(previous, next);
+
+            candidate.clear();
+            candidate.extend_from_slice(&route[..position]);
+            candidate.push(patient);
+            candidate.extend_from_slice(&route[position..]);
+            let feasible = instance.route_is_feasible(&candidate);
+
+            if best.is_none_or(|(best_feasible, _, _, best_cost)| {
+                (feasible && !best_feasible) || (feasible == best_feasible && cost < best_cost)
+            }) {
+                best = Some((feasible, route_index, position, cost));
+            }
+        }
+    }
+    let (_, route_index, position, _) = best.unwrap();
+    routes[route_index].insert(position, patient);
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum I1Seed {
+    FarthestPatient,
+    EarliestDeadline,
+}
+
+// Solomon's I1 sequential insertion heuristic. The alpha, mu and lambda parameters are
+// sampled per individual so that the population does not start out as clones.
+pub struct SolomonI1Initializer {
+    pub initial_population: usize,
+    pub problem_instance: InstanceDescription,
+    pub alpha_range: RangeInclusive<f64>,
+    pub mu_range: RangeInclusive<f64>,
+    pub lambda_range: RangeInclusive<f64>,
+    pub seed_criteria: Vec<I1Seed>,
+}
+
+impl SolomonI1Initializer {
+    fn select_seed(&self, unrouted: &[usize], criterion: I1Seed) -> usize {
+        let key = |&&id: &&usize| -> NotNan<f64> {
+            match criterion {
+                I1Seed::FarthestPatient => {
+                    NotNan::new(self.problem_instance.travel_time_depot(id)).unwrap()
+                }
+                I1Seed::EarliestDeadline => {
+                    NotNan::new(-(self.problem_instance.patients[id].end_time as f64)).unwrap()
+                }
+            }
+        };
+        *unrouted.iter().max_by_key(key).unwrap()
+    }
+
+    // Finds the feasible insertion position of a patient with the lowest c1 value
+    fn best_insertion(
+        &self,
+        route: &[usize],
+        schedule: &RouteSchedule,
+        patient: usize,
+        alpha: f64,
+        mu: f64,
+    ) -> Option<(usize, f64)> {
+        let mut best: Option<(usize, f64)> = None;
+        let mut candidate = Vec::with_capacity(route.len() + 1);
+        for position in 0..=route.len() {
+            candidate.clear();
+            candidate.extend_from_slice(&route[..position]);
+            candidate.push(patient);
+            candidate.extend_from_slice(&route[position..]);
+            if !self.problem_instance.route_is_feasible(&candidate) {
+                continue;
+            }
+            let previous = position.checked_sub(1).map(|i| route[i]);
+            let next = route.get(position).copied();
+
+            let c11 = self.problem_instance.travel_time(previous, Some(patient))
+                + self.problem_instance.travel_time(Some(patient), next)
+                - mu * self.problem_instance.travel_time(previous, next);
+            // Push forward of the service start of the successor, the depot if there is none
+            let new_schedule = self.problem_instance.route_schedule(&candidate);
+            let c12 = if next.is_some() {
+                new_schedule.service_starts[position + 1] - schedule.service_starts[position]
+            } else {
+                new_schedule.depot_arrival - schedule.depot_arrival
+            };
+            let c1 = alpha * c11 + (1. - alpha) * c12;
+            if best.is_none_or(|(_, best_c1)| c1 < best_c1) {
+                best = Some((position, c1));
+            }
+        }
+        best
+    }
+
+    fn construct(&self, rng: &mut impl Rng) -> Phenotype {
+        let alpha = rng.gen_range(self.alpha_range.clone());
+        let mu = rng.gen_range(self.mu_range.clone());
+        let lambda = rng.gen_range(self.lambda_range.clone());
+        let criterion = *self.seed_criteria.choose(rng).unwrap();
+
+        let mut unrouted: Vec<usize> = (0..self.problem_instance.patients.len()).collect();
+        let mut routes: Vec<Vec<usize>> = Vec::with_capacity(self.problem_instance.nbr_nurses);
+
+        while !unrouted.is_empty() && routes.len() < self.problem_instance.nbr_nurses {
+            let seed = self.select_seed(&unrouted, criterion);
+            unrouted.retain(|&id| id != seed);
+            let mut route = vec![seed];
+
+            loop {
+                let schedule = self.problem_instance.route_schedule(&route);
+                let mut best: Option<(usize, usize, f64)> = None;
+                for &patient in &unrouted {
+                    if let Some((position, c1)) =
+                        self.best_insertion(&route, &schedule, patient, alpha, mu)
+                    {
+                        let c2 = lambda * self.problem_instance.travel_time_depot(patient) - c1;
+                        if best.is_none_or(|(_, _, best_c2)| c2 > best_c2) {
+                            best = Some((patient, position, c2));
+                        }
+                    }
+                }
+                if let Some((patient, position, _)) = best {
+                    route.insert(position, patient);
+                    unrouted.retain(|&id| id != patient);
+                } else {
+                    break;
+                }
+            }
+            routes.push(route);
+        }
+
+        // Out of nurses, place the rest as well as possible and leave them to the penalty
+        for patient in unrouted {
+            insert_cheapest(&self.problem_instance, &mut routes, patient);
+        }
+
+        while routes.len() < self.problem_instance.nbr_nurses {
+            routes.push(Vec::new());
+        }
+        routes.shuffle(rng);
+        Phenotype(routes)
+    }
+}
+
+impl PopulationInitializer for SolomonI1Initializer {
+    fn population_size(&self) -> usize {
+        self.initial_population
+    }
+
+    fn initialize_individuals(&self, amount: usize) -> Vec<ProblemSolution> {
+        let mut rng = random::rng();
+        (0..amount)
+            .map(|_| self.construct(&mut rng).into())
+            .collect()
+    }
+}
+
+// Clarke and Wright's parallel savings heuristic, only merging routes when the merged route still
+// respects capacity and windows. Savings are perturbed by a random factor in
+// [1 - savings_perturbation, 1 + savings_perturbation] per individual for diversity.
+pub struct SavingsInitializer {
+    pub initial_population: usize,
+    pub problem_instance: InstanceDescription,
+    pub savings_perturbation: f64,
+}
+
+impl SavingsInitializer {
+    fn construct(&self, rng: &mut impl Rng) -> Phenotype {
+        let instance = &self.problem_instance;
+        let patient_amount = instance.patients.len();
+
+        let mut savings: Vec<(NotNan<f64>, usize, usize)> =
+            Vec::with_capacity(patient_amount * patient_amount);
+        for i in 0..patient_amount {
+            for j in 0..patient_amount {
+                if i == j {
+                    continue;
+                }
+                let saving = instance.travel_time_depot(i) + instance.travel_time_depot(j)
+                    - instance.travel_time_patient(i, j);
+                let noise = if self.savings_perturbation > 0. {
+                    rng.gen_range(-self.savings_perturbation..=self.savings_perturbation)
+                } else {
+                    0.
+                };
+                savings.push((NotNan::new(saving * (1. + noise)).unwrap(), i, j));
+            }
+        }
+        savings.sort_unstable_by_key(|&(saving, _, _)| std::cmp::Reverse(saving));
+
+        let mut routes: Vec<Option<Vec<usize>>> =
+            (0..patient_amount).map(|i| Some(vec![i])).collect();
+        let mut route_of: Vec<usize> = (0..patient_amount).collect();
+
+        for (_, i, j) in savings {
+            let (route_i, route_j) = (route_of[i], route_of[j]);
+            if route_i == route_j {
+                continue;
+            }
+            // Only link i -> j when i ends its route and j starts its route
+            let (first, second) = match (&routes[route_i], &routes[route_j]) {
+                (Some(first), Some(second))
+                    if first.last() == Some(&i) && second.first() == Some(&j) =>
+                {
+                    (first, second)
+                }
+                _ => continue,
+            };
+            let merged: Vec<usize> = first.iter().chain(second.iter()).copied().collect();
+            if !instance.route_is_feasible(&merged) {
+                continue;
+            }
+            for &patient in &merged {
+                route_of[patient] = route_i;
+            }
+            routes[route_i] = Some(merged);
+            routes[route_j] = None;
+        }
+
+        let mut routes: Vec<Vec<usize>> = routes.into_iter().flatten().collect();
+
+        // Too many routes for the nurses we have, dissolve the smallest ones
+        routes.sort_unstable_by_key(|route| std::cmp::Reverse(route.len()));
+        let dissolved = routes.split_off(instance.nbr_nurses.min(routes.len()));
+        for patient in dissolved.into_iter().flatten() {
+            insert_cheapest(instance, &mut routes, patient);
+        }
+
+        while routes.len() < instance.nbr_nurses {
+            routes.push(Vec::new());
+        }
+        routes.shuffle(rng);
+        Phenotype(routes)
+    }
+}
+
+impl PopulationInitializer for SavingsInitializer {
+    fn population_size(&self) -> usize {
+        self.initial_population
+    }
+
+    fn initialize_individuals(&self, amount: usize) -> Vec<ProblemSolution> {
+        let mut rng = random::rng();
+        (0..amount)
+            .map(|_| self.construct(&mut rng).into())
+            .collect()
+    }
+}
+
+// Sweeps around the depot by polar angle, starting from a random angle per individual, cutting a
+// new nurse cluster whenever the next patient would exceed the capacity. Each cluster is then
+// visited in order of window start.
+pub struct SweepInitializer {
+    pub initial_population: usize,
+    pub problem_instance: InstanceDescription,
+}
+
+impl SweepInitializer {
+    fn construct(&self, rng: &mut impl Rng) -> Phenotype {
+        let instance = &self.problem_instance;
+        let depot_x = instance.depot.x_coord as f64;
+        let depot_y = instance.depot.y_coord as f64;
+        let start_angle = rng.gen_range(0.0..TAU);
+
+        let mut angles: Vec<(NotNan<f64>, usize)> = instance
+            .patients
+            .iter()
+            .enumerate()
+            .map(|(id, patient)| {
+                let angle =
+                    (patient.y_coord as f64 - depot_y).atan2(patient.x_coord as f64 - depot_x);
+                (
+                    NotNan::new((angle - start_angle).rem_euclid(TAU)).unwrap(),
+                    id,
+                )
+            })
+            .collect();
+        angles.sort_unstable();
+
+        let mut routes: Vec<Vec<usize>> = vec![Vec::new()];
+        let mut leftover = Vec::new();
+        let mut demand = 0;
+        for (_, id) in angles {
+            let patient_demand = instance.patients[id].demand;
+            if demand + patient_demand > instance.capacity_nurse
+                && !routes.last().unwrap().is_empty()
+            {
+                if routes.len() == instance.nbr_nurses {
+                    leftover.push(id);
+                    continue;
+                }
+                routes.push(Vec::new());
+                demand = 0;
+            }
+            routes.last_mut().unwrap().push(id);
+            demand += patient_demand;
+        }
+
+        for route in routes.iter_mut() {
+            route.sort_by_key(|&id| instance.patients[id].start_time);
+        }
+        for patient in leftover {
+            insert_cheapest(instance, &mut routes, patient);
+        }
+
+        while routes.len() < instance.nbr_nurses {
+            routes.push(Vec::new());
+        }
+        routes.shuffle(rng);
+        Phenotype(routes)
+    }
+}
+
+impl PopulationInitializer for SweepInitializer {
+    fn population_size(&self) -> usize {
+        self.initial_population
+    }
+
+    fn initialize_individuals(&self, amount: usize) -> Vec<ProblemSolution> {
+        let mut rng = random::rng();
+        (0..amount)
+            .map(|_| self.construct(&mut rng).into())
+            .collect()
+    }
+}
+
+// Starts from previously found solutions, e.g. the best result of an earlier run. The seeds are
+// kept as is, a share of the remaining population are perturbed copies of them and the rest are
+// random individuals.
+pub struct SeededInitializer {
+    pub initial_population: usize,
+    pub seeds: Vec<ProblemSolution>,
+    pub perturbed_proportion: f64,
+    pub perturbation_moves: usize,
+    pub patient_amount: usize,
+    pub nurses: usize,
+}
+
+impl SeededInitializer {
+    pub fn from_files<P>(
+        paths: &[P],
+        instance: &InstanceDescription,
+        initial_population: usize,
+        perturbed_proportion: f64,
+        perturbation_moves: usize,
+    ) -> SeededInitializer
+    where
+        P: AsRef<Path>,
+    {
+        let seeds = paths
+            .iter()
+            .map(|path| {
+                let phenotype = Phenotype::from_file(path, instance.nbr_nurses);
+                let mut patients: Vec<usize> = phenotype.0.iter().flatten().copied().collect();
+                patients.sort_unstable();
+                assert!(
+                    patients == (0..instance.patients.len()).collect::<Vec<_>>(),
+                    "{} is not a solution to {}",
+                    path.as_ref().display(),
+                    instance.instance_name
+                );
+                phenotype.into()
+            })
+            .collect();
+        SeededInitializer {
+            initial_population,
+            seeds,
+            perturbed_proportion,
+            perturbation_moves,
+            patient_amount: instance.patients.len(),
+            nurses: instance.nbr_nurses,
+        }
+    }
+}
+
+impl PopulationInitializer for SeededInitializer {
+    fn population_size(&self) -> usize {
+        self.initial_population
+    }
+
+    fn initialize_individuals(&self, amount: usize) -> Vec<ProblemSolution> {
+        let mut rng = random::rng();
+        let mut population: Vec<ProblemSolution> =
+            self.seeds.iter().take(amount).cloned().collect();
+
+        let remaining = amount - population.len();
+        let perturbed = if self.seeds.is_empty() {
+            0
+        } else {
+            (remaining as f64 * self.perturbed_proportion).round() as usize
+        };
+        for _ in 0..perturbed {
+            let mut individual = self.seeds.choose(&mut rng).unwrap().clone();
+            // Same move as the insertion mutator
+            for _ in 0..self.perturbation_moves {
+                let removed = individual.0.remove(rng.gen_range(0..individual.0.len()));
+                let insertion_index = rng.gen_range(0..individual.0.len());
+                individual.0.insert(insertion_index, removed);
+            }
+            population.push(individual);
+        }
+
+        let random = DefaultInitializer {
+            initial_population: amount - population.len(),
+            patient_amount: self.patient_amount,
+            nurses: self.nurses,
+        };
+        population.append(&mut random.initialize_population());
+        population
+    }
+}
+
+// Builds the population from several initializers, each contributing its proportion of the
+// individuals. Duplicates are rebuilt up to max_attempts times before falling back to random
+// individuals.
+pub struct MixedInitializer {
+    pub initial_population: usize,
+    pub initializers: Vec<(f64, Box<dyn PopulationInitializer>)>,
+    pub max_attempts: usize,
+    pub patient_amount: usize,
+    pub nurses: usize,
+    pub report: bool,
+}
+
+impl MixedInitializer {
+    // Builds the individuals, reporting on them if asked to
+    fn create(&self, amount: usize, report: bool) -> Vec<ProblemSolution> {
+        // Solutions with the same routes are duplicates whichever nurses drive them
+        let mut seen: HashSet<Vec<Vec<usize>>> = HashSet::with_capacity(amount);
+        let mut population: Vec<ProblemSolution> = Vec::with_capacity(amount);
+        let random = DefaultInitializer {
+            initial_population: 1,
+            patient_amount: self.patient_amount,
+            nurses: self.nurses,
+        };
+
+        for (index, ((_, initializer), share)) in self
+            .initializers
+            .iter()
+            .zip(self.shares(amount))
+            .enumerate()
+        {
+            let mut created = 0;
+            let mut duplicates = 0;
+            let mut attempts = 0;
+            while created < share {
+                let missing = share - created;
+                let candidates = if attempts < self.max_attempts {
+                    initializer.initialize_individuals(missing)
+                } else {
+                    random.initialize_individuals(missing)
+                };
+                attempts += 1;
+                for candidate in candidates {
+                    if seen.insert(canonical_routes(&candidate)) {
+                        population.push(candidate);
+                        created += 1;
+                    } else {
+                        duplicates += 1;
+                    }
+                }
+            }
+            if report {
+                println!(
+                    "Initializer {}: {} individuals, {} duplicates rebuilt",
+                    index, share, duplicates
+                );
+            }
+        }
+
+        if report && population.len() > 1 {
+            let individuals: Vec<&ProblemSolution> = population.iter().collect();
+            let distances = BrokenPairs.pairwise(&individuals);
+            println!(
+                "Initial population: {} individuals, average broken pairs distance {:.2}",
+                population.len(),
+                distances.iter().sum::<f64>() / distances.len() as f64
+            );
+        }
+        population
+    }
+
+    fn shares(&self, amount: usize) -> Vec<usize> {
+        let total: f64 = self
+            .initializers
+            .iter()
+            .map(|(proportion, _)| proportion)
+            .sum();
+        let mut shares: Vec<usize> = self
+            .initializers
+            .iter()
+            .map(|(proportion, _)| (amount as f64 * proportion / total).round() as usize)
+            .collect();
+        // Rounding up can hand out too many, which are taken from the largest shares, and
+        // rounding down too few, which go to the last initializer
+        let mut assigned: usize = shares.iter().sum();
+        while assigned > amount {
+            *shares.iter_mut().max().unwrap() -= 1;
+            assigned -= 1;
+        }
+        if let Some(last) = shares.last_mut() {
+            *last += amount - assigned;
+        }
+        shares
+    }
+}
+
+impl PopulationInitializer for MixedInitializer {
+    fn population_size(&self) -> usize {
+        self.initial_population
+    }
+
+    // Only the initial population is reported on, not the individuals the GA asks for while it
+    // runs
+    fn initialize_individuals(&self, amount: usize) -> Vec<ProblemSolution> {
+        self.create(amount, false)
+    }
+
+    fn initialize_population(&self) -> Vec<ProblemSolution> {
+        self.create(self.initial_population, self.report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mixed(proportions: &[f64]) -> MixedInitializer {
+        MixedInitializer {
+            initial_population: 0,
+            initializers: proportions
+                .iter()
+                .map(|&proportion| {
+                    let initializer: Box<dyn PopulationInitializer> =
+                        Box::new(DefaultInitializer {
+                            initial_population: 0,
+                            patient_amount: 5,
+                            nurses: 2,
+                        });
+                    (proportion, initializer)
+                })
+                .collect(),
+            max_attempts: 0,
+            patient_amount: 5,
+            nurses: 2,
+            report: false,
+        }
+    }
+
+    #[test]
+    fn shares_add_up_to_the_amount() {
+        assert_eq!(mixed(&[0.5, 0.5, 0.]).shares(3), vec![2, 1, 0]);
+        assert_eq!(mixed(&[0.25, 0.25, 0.25, 0.25]).shares(2), vec![1, 1, 0, 0]);
+        assert_eq!(mixed(&[1., 1., 1.]).shares(10), vec![3, 3, 4]);
+        for amount in 0..50 {
+            for proportions in [[0.5, 0.5, 0.], [0.3, 0.3, 0.4], [0.15, 0.15, 0.7]] {
+                assert_eq!(
+                    mixed(&proportions).shares(amount).iter().sum::<usize>(),
+                    amount
+                );
+            }
+        }
+    }
+}
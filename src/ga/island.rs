@@ -0,0 +1,185 @@
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use ordered_float::NotNan;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::{IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+
+use super::checkpoint::GAState;
+use super::problem::{InstanceDescription, ProblemSolution};
+use super::{GAResult, GARun, Genome, GA};
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum Topology {
+    Ring,
+    FullyConnected,
+    Random,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum MigrantSelection {
+    Best,
+    Random,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum MigrantReplacement {
+    Worst,
+    Random,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "G: Genome"))]
+pub struct IslandState<G = ProblemSolution> {
+    pub generation: usize,
+    pub rng: ChaCha8Rng,
+    pub islands: Vec<GAState<G>>,
+}
+
+pub struct IslandCheckpoint {
+    pub interval: usize,
+    pub path: PathBuf,
+}
+
+// Evolves the islands in parallel, exchanging migrants every migration_interval generations. The
+// islands may be set up with different operators.
+pub struct IslandModel<'a, G = ProblemSolution, P = InstanceDescription> {
+    pub islands: Vec<GA<'a, G, P>>,
+    pub migration_interval: usize,
+    pub migrants: usize,
+    pub topology: Topology,
+    pub migrant_selection: MigrantSelection,
+    pub replacement: MigrantReplacement,
+    pub rng: ChaCha8Rng,
+    pub checkpoint: Option<IslandCheckpoint>,
+    pub resume_from: Option<IslandState<G>>,
+}
+
+impl<'a, G: Genome, P: Clone + Send + Sync> IslandModel<'a, G, P> {
+    pub fn start(mut self) -> Vec<GAResult<G>> {
+        let mut generation = 0;
+        if let Some(state) = self.resume_from.take() {
+            generation = state.generation;
+            self.rng = state.rng;
+            for (island, island_state) in self.islands.iter_mut().zip(state.islands) {
+                island.resume_from = Some(island_state);
+            }
+        }
+
+        let islands = std::mem::take(&mut self.islands);
+        let mut runs: Vec<GARun<G, P>> = islands.into_par_iter().map(GA::begin).collect();
+
+        while runs.iter().any(|run| !run.finished) {
+            runs.par_iter_mut().for_each(|run| {
+                for _ in 0..self.migration_interval {
+                    if !run.step() {
+                        break;
+                    }
+                }
+            });
+            generation += self.migration_interval;
+            if runs.iter().all(|run| run.finished) {
+                break;
+            }
+            self.migrate(&mut runs);
+
+            if let Some(checkpoint) = &self.checkpoint {
+                let previous = generation - self.migration_interval;
+                if generation / checkpoint.interval > previous / checkpoint.interval {
+                    IslandState {
+                        generation,
+                        rng: self.rng.clone(),
+                        islands: runs.iter().map(GARun::state).collect(),
+                    }
+                    .write(&checkpoint.path);
+                }
+            }
+        }
+        runs.into_iter().map(GARun::finish).collect()
+    }
+
+    fn select_migrants(&mut self, run: &GARun<G, P>) -> Vec<G> {
+        let population = &run.ga.population;
+        let amount = self.migrants.min(population.len());
+        let indices: Vec<usize> = match self.migrant_selection {
+            MigrantSelection::Best => {
+                let mut indices: Vec<usize> = (0..population.len()).collect();
+                indices.sort_by_key(|&i| {
+                    std::cmp::Reverse(NotNan::new(run.ga.population_eval[i]).unwrap())
+                });
+                indices.truncate(amount);
+                indices
+            }
+            MigrantSelection::Random => {
+                (0..population.len()).choose_multiple(&mut self.rng, amount)
+            }
+        };
+        indices.into_iter().map(|i| population[i].clone()).collect()
+    }
+
+    fn receive_migrants(&mut self, run: &mut GARun<G, P>, migrants: &[G]) {
+        for migrant in migrants {
+            let index = match self.replacement {
+                MigrantReplacement::Worst => run
+                    .ga
+                    .population_eval
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|&(_, &eval)| NotNan::new(eval).unwrap())
+                    .map(|(i, _)| i)
+                    .unwrap(),
+                MigrantReplacement::Random => self.rng.gen_range(0..run.ga.population.len()),
+            };
+            run.replace_individual(index, migrant.clone());
+        }
+    }
+
+    fn migrate(&mut self, runs: &mut [GARun<G, P>]) {
+        let island_amount = runs.len();
+        if island_amount < 2 || self.migrants == 0 {
+            return;
+        }
+        // Pick every island's emigrants before any arrive, so migrants only travel one hop
+        let outgoing: Vec<Vec<G>> = runs.iter().map(|run| self.select_migrants(run)).collect();
+
+        for (source, migrants) in outgoing.iter().enumerate() {
+            let others = (0..island_amount).filter(|&target| target != source);
+            let targets: Vec<usize> = match self.topology {
+                Topology::Ring => vec![(source + 1) % island_amount],
+                Topology::FullyConnected => others.collect(),
+                Topology::Random => others.choose(&mut self.rng).into_iter().collect(),
+            };
+            for target in targets {
+                if !runs[target].finished {
+                    self.receive_migrants(&mut runs[target], migrants);
+                }
+            }
+        }
+    }
+}
+
+impl<G: Genome> IslandState<G> {
+    pub fn from_file<P>(path: P) -> IslandState<G>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path).unwrap();
+        serde_json::from_reader(BufReader::new(file)).unwrap()
+    }
+
+    pub fn write<P>(&self, path: P)
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, serde_json::to_string(self).unwrap()).unwrap();
+        fs::rename(temporary, path).unwrap();
+    }
+}
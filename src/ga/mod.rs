@@ -1,126 +1,568 @@
-use ordered_float::NotNan;
-use std::ops::Add;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
 
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub mod archive;
+pub mod biased_fitness;
+pub mod cache;
+pub mod checkpoint;
 pub mod crossover;
+pub mod distance;
+pub mod diversity;
 pub mod fitness_function;
+pub mod individual;
 pub mod initializer;
+pub mod island;
 pub mod mutator;
+pub mod niching;
+pub mod observer;
 pub mod parent_selector;
 pub mod penalty_function;
 pub mod problem;
+pub mod random;
 pub mod repair_mechanism;
+pub mod route;
+pub mod statistics;
+pub mod steady_state;
 pub mod survivor_selection;
+pub mod termination;
+
+#[cfg(test)]
+mod tests;
+
+// What the engine needs from a genome: hashing for the evaluation cache, serde for checkpoints
+// and a default distance between two genomes for selectors that do not take a DistanceMetric
+pub trait Genome: Clone + Eq + Hash + Debug + Serialize + DeserializeOwned + Send + Sync {
+    fn distance(&self, other: &Self) -> f64;
+}
+
+impl Genome for problem::ProblemSolution {
+    fn distance(&self, other: &Self) -> f64 {
+        survivor_selection::TournamentSelector::dist(self, other) as f64
+    }
+}
 
-pub fn elementwise_addition<N, IA, IB, F>(a: IA, b: IB) -> F
-where
-    N: Add,
-    IA: IntoIterator<Item = N>,
-    IB: IntoIterator<Item = N>,
-    F: FromIterator<N> + FromIterator<<N as Add>::Output>,
-{
-    a.into_iter().zip(b).map(|(a, b)| a + b).collect()
+fn count_changed<G: Genome>(before: &[G], after: &[G]) -> usize {
+    before.iter().zip(after).filter(|(a, b)| a != b).count()
 }
 
+// Fitness and penalty of one individual, computed together
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Evaluation {
+    pub fitness: f64,
+    pub penalty: f64,
+    // The penalty split up by constraint, see PenaltyFunction::violation_names
+    pub violations: Vec<f64>,
+}
+
+impl Evaluation {
+    pub fn eval(&self) -> f64 {
+        self.fitness + self.penalty
+    }
+}
+
+// The engine is generic over the genome G and the problem P it is evaluated against, the VRP
+// types are the defaults
 #[derive(Clone)]
-pub struct GA<'a> {
-    pub instance_description: problem::InstanceDescription,
-
-    pub population_initializer: &'a dyn initializer::PopulationInitializer,
-    pub fitness_function: &'a dyn fitness_function::FitnessFunction,
-    pub penalty_function: Option<&'a dyn penalty_function::PenaltyFunction>,
-    pub parent_selector: &'a dyn parent_selector::ParentSelector,
-    pub crossover_system: &'a dyn crossover::ParentCrossoverSystem,
-    pub mutator: &'a dyn mutator::Mutator,
-    pub repair_mechanism: Option<&'a dyn repair_mechanism::RepairMechanism>,
-    pub survivor_selector: &'a dyn survivor_selection::SurvivorSelector,
-
-    pub population: Vec<problem::ProblemSolution>,
+pub struct GA<'a, G = problem::ProblemSolution, P = problem::InstanceDescription> {
+    pub instance_description: P,
+
+    pub population_initializer: &'a dyn initializer::PopulationInitializer<G>,
+    pub fitness_function: &'a dyn fitness_function::FitnessFunction<G, P>,
+    pub penalty_function: Option<&'a dyn penalty_function::PenaltyFunction<G, P>>,
+    pub parent_selector: &'a dyn parent_selector::ParentSelector<G>,
+    pub crossover_system: &'a dyn crossover::ParentCrossoverSystem<G>,
+    pub mutator: &'a dyn mutator::Mutator<G>,
+    pub repair_mechanism: Option<&'a dyn repair_mechanism::RepairMechanism<G, P>>,
+    pub survivor_selector: &'a dyn survivor_selection::SurvivorSelector<G>,
+    pub termination: &'a dyn termination::TerminationCriterion,
+    pub observers: Vec<&'a dyn observer::GenerationObserver<G>>,
+    // Distance between individuals for the diversity statistic and similarity based replacement
+    pub distance_metric: &'a dyn distance::DistanceMetric<G>,
+    // Children bred each generation, the population size if None. Crossover pairs parents, so
+    // this has to be even.
+    pub offspring: Option<usize>,
+    // Breed a few children at a time instead of whole generations, which replaces the survivor
+    // selector and offspring
+    pub steady_state: Option<steady_state::SteadyState>,
+    // Duplicate elimination and restarts
+    pub diversity: Option<diversity::DiversityManagement>,
+    // Select by cost and diversity contribution instead of the eval
+    pub biased_fitness: Option<biased_fitness::BiasedFitness>,
+    // Fitness sharing or clearing of the evals selection sees
+    pub niching: Option<&'a dyn niching::Niching>,
+
+    pub population: Vec<G>,
     pub population_eval: Vec<f64>,
     pub population_penalites: Vec<f64>,
-    pub children: Vec<problem::ProblemSolution>,
+    pub population_violations: Vec<Vec<f64>>,
+    // Age, origin and parents of each individual
+    pub population_metadata: Vec<individual::Metadata>,
+    pub children: Vec<G>,
     pub children_eval: Vec<f64>,
+    pub children_penalties: Vec<f64>,
+    pub statistics: statistics::StatisticsHistory,
+    pub hall_of_fame: archive::HallOfFame<G>,
+    pub cache: cache::EvaluationCache<G>,
+
+    pub rng: ChaCha8Rng,
+    pub resume_from: Option<checkpoint::GAState<G>>,
 }
 
-impl GA<'_> {
-    pub fn start(
-        mut self,
-        generations: usize,
-        diagnostics: bool,
-        diagnostics_interval: usize,
-    ) -> (Vec<problem::ProblemSolution>, Vec<f64>, Vec<f64>) {
-        self.population = self.population_initializer.initialize_population();
-
-        for i in 0..generations {
-            self.population_eval = self.eval_pop(&self.population);
-            self.population_penalites = self.get_penalties(&self.population);
-
-            if diagnostics && i % diagnostics_interval == 0 {
-                let average_fitnesses: f64 =
-                    self.population_eval.iter().sum::<f64>() / self.population_eval.len() as f64;
-                let average_penalty: f64 = self.population_penalites.iter().sum::<f64>()
-                    / self.population_penalites.len() as f64;
-                let max_fitness: f64 = self
-                    .population_eval
-                    .iter()
-                    .copied()
-                    .flat_map(NotNan::new)
-                    .max()
-                    .map(NotNan::into_inner)
-                    .unwrap();
-                let best_penalty: f64 = self
-                    .population_penalites
-                    .iter()
-                    .copied()
-                    .flat_map(NotNan::new)
-                    .max()
-                    .map(NotNan::into_inner)
-                    .unwrap();
-                println!(
-                    "Gen: {}, average fitness: {:.2}, max fitness: {:.2}, average penalty: {:.2}, best_penalty: {:.2}",
-                    i, average_fitnesses, max_fitness, average_penalty, best_penalty
-                );
+pub struct GAResult<G = problem::ProblemSolution> {
+    pub population: Vec<G>,
+    pub population_eval: Vec<f64>,
+    pub population_penalties: Vec<f64>,
+    pub statistics: statistics::StatisticsHistory,
+    pub hall_of_fame: archive::HallOfFame<G>,
+}
+
+// A GA in progress, advanced one generation at a time by step
+pub struct GARun<'a, G = problem::ProblemSolution, P = problem::InstanceDescription> {
+    pub ga: GA<'a, G, P>,
+    pub generation: usize,
+    pub finished: bool,
+    start_time: Instant,
+    elapsed_before_start: Duration,
+    evaluations: usize,
+    generations_without_improvement: usize,
+    // Generation of the last diversity restart
+    last_restart: usize,
+    // Id of the next individual
+    next_id: usize,
+    // Whether a child found a new best since the last generation was recorded, the survivor
+    // selector may have thrown it away since
+    children_improved_best: bool,
+}
+
+impl<'a, G: Genome, P: Clone + Send + Sync> GA<'a, G, P> {
+    pub fn start(self) -> GAResult<G> {
+        let mut run = self.begin();
+        while run.step() {}
+        run.finish()
+    }
+
+    // Initializes and evaluates the population, or picks up where a checkpoint left off
+    pub fn begin(mut self) -> GARun<'a, G, P> {
+        if let Some(state) = self.resume_from.take() {
+            self.population = state.population;
+            self.population_eval = state.population_eval;
+            self.population_penalites = state.population_penalties;
+            self.population_violations = state.population_violations;
+            self.rng = state.rng;
+            self.statistics = state.statistics;
+            self.hall_of_fame = state.hall_of_fame;
+            self.population_metadata = state.population_metadata;
+            self.cache = state.cache;
+            // A finished run has already told its observers it ended
+            if !state.finished {
+                for observer in &self.observers {
+                    observer.on_start(&self.population);
+                }
             }
+            let run = GARun {
+                ga: self,
+                generation: state.generation,
+                finished: state.finished,
+                start_time: Instant::now(),
+                elapsed_before_start: Duration::from_secs_f64(state.elapsed_seconds),
+                evaluations: state.evaluations,
+                generations_without_improvement: state.generations_without_improvement,
+                last_restart: state.last_restart,
+                next_id: state.next_id,
+                children_improved_best: false,
+            };
+            return run;
+        }
+
+        self.population = random::scoped(&mut self.rng, || {
+            self.population_initializer.initialize_population()
+        });
+        for observer in &self.observers {
+            observer.on_start(&self.population);
+        }
+        let mut run = GARun {
+            ga: self,
+            generation: 0,
+            finished: false,
+            start_time: Instant::now(),
+            elapsed_before_start: Duration::ZERO,
+            evaluations: 0,
+            generations_without_improvement: 0,
+            last_restart: 0,
+            next_id: 0,
+            children_improved_best: false,
+        };
+        run.ga.population_metadata = (0..run.ga.population.len())
+            .map(|_| run.new_metadata(individual::Origin::Initializer, Vec::new()))
+            .collect();
+        run.evaluate_population();
+        run.record_generation();
+        run
+    }
+
+    pub fn evaluate_one(&self, solution: &G) -> Evaluation {
+        let fitness = self
+            .fitness_function
+            .get_fitness(solution, &self.instance_description);
+        let (penalty, violations) = self.penalty_function.map_or_else(Default::default, |pf| {
+            pf.get_penalty_and_violations(solution, &self.instance_description)
+        });
+        Evaluation {
+            fitness,
+            penalty,
+            violations,
+        }
+    }
 
-            let selected_parents = self
-                .parent_selector
-                .select_parents(&self.population, &self.population_eval);
-            let children = self
-                .mutator
-                .mutate(&self.crossover_system.cross_over(&selected_parents));
-            if let Some(repair) = self.repair_mechanism {
-                self.children = repair.repair(&children, &self.instance_description);
-            } else {
-                self.children = children;
+    // Evaluates the individuals in parallel, but only computes the genomes that are not in the
+    // cache. The results keep the order of the population, and how many evaluations that took is
+    // returned with them.
+    pub fn evaluate_cached(&mut self, population: &[G]) -> (Vec<Evaluation>, usize) {
+        let cached: Vec<Option<Evaluation>> = population
+            .iter()
+            .map(|solution| self.cache.get(solution))
+            .collect();
+        let mut missing: Vec<&G> = Vec::new();
+        let mut missing_index: HashMap<&G, usize> = HashMap::new();
+        for (solution, evaluation) in population.iter().zip(&cached) {
+            if evaluation.is_none() {
+                missing_index.entry(solution).or_insert_with(|| {
+                    missing.push(solution);
+                    missing.len() - 1
+                });
             }
+        }
+        let computed: Vec<Evaluation> = missing
+            .par_iter()
+            .map(|solution| self.evaluate_one(solution))
+            .collect();
+        for (solution, evaluation) in missing.iter().zip(&computed) {
+            self.cache.insert((*solution).clone(), evaluation.clone());
+        }
 
-            self.children_eval = self.eval_pop(&self.children);
+        let evaluations = population
+            .iter()
+            .zip(cached)
+            .map(|(solution, evaluation)| {
+                evaluation.unwrap_or_else(|| computed[missing_index[solution]].clone())
+            })
+            .collect();
+        (evaluations, computed.len())
+    }
 
-            self.population = self.survivor_selector.select_survivors(
-                &self.population,
-                &self.population_eval,
-                &self.children,
-                &self.children_eval,
-            );
+    // The values selectors rank parents and children by. With niching or biased fitness turned
+    // on they are computed over both groups together, otherwise they are the evals.
+    pub fn selection_eval(
+        &self,
+        population: &[G],
+        population_eval: &[f64],
+        children: &[G],
+        children_eval: &[f64],
+    ) -> (Vec<f64>, Vec<f64>) {
+        if !self.selection_uses_distances() {
+            return (population_eval.to_vec(), children_eval.to_vec());
         }
-        let eval = self.eval_pop(&self.population);
-        let penalties = self.get_penalties(&self.population);
-        (self.population, eval, penalties)
+        let individuals: Vec<&G> = population.iter().chain(children).collect();
+        let distances = distance::distance_matrix(self.distance_metric, &individuals);
+        let mut selection_eval =
+            self.adjust_selection_eval([population_eval, children_eval].concat(), &distances);
+        let children_selection_eval = selection_eval.split_off(population.len());
+        (selection_eval, children_selection_eval)
     }
 
-    pub fn eval_pop(&self, population: &[problem::ProblemSolution]) -> Vec<f64> {
-        let mut fitness = self
-            .fitness_function
-            .get_fitnesses(population, &self.instance_description);
-        if let Some(pf) = self.penalty_function {
-            fitness = elementwise_addition(
-                fitness,
-                pf.get_penalties(population, &self.instance_description),
-            );
-        }
-        fitness
-    }
-    pub fn get_penalties(&self, population: &[problem::ProblemSolution]) -> Vec<f64> {
-        let pf = self.penalty_function.unwrap();
-        pf.get_penalties(population, &self.instance_description)
+    // Whether the selection evals depend on the distances between individuals
+    pub fn selection_uses_distances(&self) -> bool {
+        self.niching.is_some() || self.biased_fitness.is_some()
+    }
+
+    // Applies niching and biased fitness to the evals, distances is the distance matrix of the
+    // individuals
+    pub fn adjust_selection_eval(&self, mut eval: Vec<f64>, distances: &[Vec<f64>]) -> Vec<f64> {
+        if let Some(niching) = self.niching {
+            eval = niching.adjust(distances, &eval);
+        }
+        if let Some(biased_fitness) = self.biased_fitness {
+            eval = biased_fitness.evaluate(&eval, distances);
+        }
+        eval
+    }
+}
+
+impl<'a, G: Genome, P: Clone + Send + Sync> GARun<'a, G, P> {
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed_before_start + self.start_time.elapsed()
+    }
+
+    // Puts a migrant from another island into the population
+    pub fn replace_individual(&mut self, index: usize, solution: G) {
+        let (mut evaluation, computed) = self.ga.evaluate_cached(std::slice::from_ref(&solution));
+        self.evaluations += computed;
+        let metadata = self.new_metadata(individual::Origin::Migration, Vec::new());
+        self.put_individual(index, solution, evaluation.remove(0), metadata);
+    }
+
+    fn put_individual(
+        &mut self,
+        index: usize,
+        solution: G,
+        evaluation: Evaluation,
+        metadata: individual::Metadata,
+    ) {
+        self.ga.population_eval[index] = evaluation.eval();
+        self.ga.population_penalites[index] = evaluation.penalty;
+        self.ga.population_violations[index] = evaluation.violations;
+        self.ga.population_metadata[index] = metadata;
+        self.ga.population[index] = solution;
+    }
+
+    fn evaluate_population(&mut self) {
+        let population = std::mem::take(&mut self.ga.population);
+        let (evaluations, computed) = self.ga.evaluate_cached(&population);
+        self.ga.population = population;
+        self.ga.population_eval = evaluations.iter().map(Evaluation::eval).collect();
+        self.ga.population_penalites = evaluations.iter().map(|e| e.penalty).collect();
+        self.ga.population_violations = evaluations.into_iter().map(|e| e.violations).collect();
+        self.evaluations += computed;
+    }
+
+    // Snapshot between generations, after the current one has been recorded
+    pub fn state(&self) -> checkpoint::GAState<G> {
+        checkpoint::GAState {
+            generation: self.generation,
+            finished: self.finished,
+            elapsed_seconds: self.elapsed().as_secs_f64(),
+            evaluations: self.evaluations,
+            generations_without_improvement: self.generations_without_improvement,
+            population: self.ga.population.clone(),
+            population_eval: self.ga.population_eval.clone(),
+            population_penalties: self.ga.population_penalites.clone(),
+            population_violations: self.ga.population_violations.clone(),
+            rng: self.ga.rng.clone(),
+            statistics: self.ga.statistics.clone(),
+            hall_of_fame: self.ga.hall_of_fame.clone(),
+            population_metadata: self.ga.population_metadata.clone(),
+            last_restart: self.last_restart,
+            next_id: self.next_id,
+            cache: self.ga.cache.clone(),
+        }
+    }
+
+    // Updates the archive, statistics and observers with the evaluated population, and decides
+    // whether the run is over
+    fn record_generation(&mut self) {
+        let ga = &mut self.ga;
+        let elapsed = self.elapsed_before_start + self.start_time.elapsed();
+
+        let improved = ga.hall_of_fame.update(
+            &ga.population,
+            &ga.population_eval,
+            &ga.population_penalites,
+            self.generation,
+        ) | std::mem::take(&mut self.children_improved_best);
+        if improved {
+            self.generations_without_improvement = 0;
+        } else {
+            self.generations_without_improvement += 1;
+        }
+        let best = ga.hall_of_fame.best().unwrap();
+
+        let mut generation_statistics = statistics::GenerationStatistics::from_population(
+            self.generation,
+            elapsed.as_secs_f64(),
+            &ga.population,
+            &ga.population_eval,
+            &ga.population_penalites,
+            ga.distance_metric,
+        );
+        generation_statistics.set_mean_violations(
+            ga.penalty_function
+                .map_or_else(Vec::new, |pf| pf.violation_names()),
+            &ga.population_violations,
+        );
+        generation_statistics.cache_hit_rate = ga.cache.take_hit_rate();
+        ga.statistics.0.push(generation_statistics);
+
+        let report = observer::GenerationReport {
+            generation: self.generation,
+            elapsed,
+            population: &ga.population,
+            population_eval: &ga.population_eval,
+            population_penalties: &ga.population_penalites,
+            best_fitness: best.fitness,
+            best_solution: &best.solution,
+        };
+        let mut stop_requested = false;
+        for observer in &ga.observers {
+            if improved {
+                observer.on_new_best(&report);
+            }
+            stop_requested |= observer.on_generation(&report).is_break();
+        }
+
+        let state = termination::RunState {
+            generation: self.generation,
+            elapsed,
+            evaluations: self.evaluations,
+            best_fitness: best.fitness,
+            generations_without_improvement: self.generations_without_improvement,
+            population_eval: &ga.population_eval,
+        };
+        if stop_requested || ga.termination.should_terminate(&state) {
+            for observer in &ga.observers {
+                observer.on_end(&report);
+            }
+            self.finished = true;
+        }
+    }
+
+    // Breeds the next generation, returns false once the run is over
+    pub fn step(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+        if let Some(steady_state) = self.ga.steady_state {
+            self.steady_state_step(steady_state);
+            self.generation += 1;
+            self.ga.cache.refresh(&self.ga.population);
+            if let Some(diversity) = self.ga.diversity {
+                self.manage_diversity(diversity);
+            }
+            self.record_generation();
+            return !self.finished;
+        }
+        let ga = &mut self.ga;
+
+        let (parent_selection_eval, _) =
+            ga.selection_eval(&ga.population, &ga.population_eval, &[], &[]);
+        let (selected_parents, crossed_over, children, repaired) =
+            random::scoped(&mut ga.rng, || {
+                let amount = ga.offspring.unwrap_or(ga.population.len());
+                let selected_parents = ga
+                    .parent_selector
+                    .select_parents_with_penalties(
+                        &ga.population,
+                        &ga.population_eval,
+                        &ga.population_penalites,
+                        amount,
+                    )
+                    .unwrap_or_else(|| {
+                        ga.parent_selector.select_parents(
+                            &ga.population,
+                            &parent_selection_eval,
+                            amount,
+                        )
+                    });
+                let crossed_over = ga.crossover_system.cross_over(&selected_parents);
+                let children = ga.mutator.mutate(&crossed_over);
+                let repaired = if let Some(repair) = ga.repair_mechanism {
+                    repair.repair(&children, &ga.instance_description)
+                } else {
+                    children.clone()
+                };
+                (selected_parents, crossed_over, children, repaired)
+            });
+        let children_metadata =
+            self.children_metadata(&selected_parents, &crossed_over, &children, &repaired);
+        let ga = &mut self.ga;
+        let (children_evaluations, computed) = ga.evaluate_cached(&repaired);
+        ga.children = repaired;
+        ga.children_eval = children_evaluations.iter().map(Evaluation::eval).collect();
+        ga.children_penalties = children_evaluations.iter().map(|e| e.penalty).collect();
+        let children_violations: Vec<Vec<f64>> = children_evaluations
+            .into_iter()
+            .map(|e| e.violations)
+            .collect();
+        self.evaluations += computed;
+        self.children_improved_best |= ga.hall_of_fame.update(
+            &ga.children,
+            &ga.children_eval,
+            &ga.children_penalties,
+            self.generation + 1,
+        );
+
+        let generation_statistics = ga.statistics.0.last_mut().unwrap();
+        generation_statistics.crossover_applied = count_changed(&selected_parents, &crossed_over);
+        generation_statistics.mutation_applied = count_changed(&crossed_over, &children);
+        generation_statistics.repair_applied = count_changed(&children, &ga.children);
+        generation_statistics.children_improved = individual::count_improved(
+            &ga.children_eval,
+            &children_metadata,
+            &ga.population_eval,
+            &ga.population_metadata,
+        );
+
+        let (population_selection_eval, children_selection_eval) = ga.selection_eval(
+            &ga.population,
+            &ga.population_eval,
+            &ga.children,
+            &ga.children_eval,
+        );
+        let age = |metadata: &individual::Metadata| metadata.age(self.generation + 1);
+        let parent_ages: Vec<usize> = ga.population_metadata.iter().map(age).collect();
+        let children_ages: Vec<usize> = children_metadata.iter().map(age).collect();
+        let survivors = random::scoped(&mut ga.rng, || {
+            ga.survivor_selector
+                .select_survivors_with_ages(
+                    &ga.population,
+                    &population_selection_eval,
+                    &parent_ages,
+                    &ga.children,
+                    &children_selection_eval,
+                    &children_ages,
+                )
+                .or_else(|| {
+                    ga.survivor_selector.select_survivors_with_penalties(
+                        &ga.population,
+                        &ga.population_eval,
+                        &ga.population_penalites,
+                        &ga.children,
+                        &ga.children_eval,
+                        &ga.children_penalties,
+                    )
+                })
+                .unwrap_or_else(|| {
+                    ga.survivor_selector.select_survivors(
+                        &ga.population,
+                        &population_selection_eval,
+                        &ga.children,
+                        &children_selection_eval,
+                    )
+                })
+        });
+        // The survivors keep the evaluations they were selected with
+        let sources = individual::survivor_sources(&survivors, &ga.population, &ga.children);
+        ga.population_metadata =
+            individual::for_survivors(&sources, &ga.population_metadata, &children_metadata);
+        ga.population_eval =
+            individual::for_survivors(&sources, &ga.population_eval, &ga.children_eval);
+        ga.population_penalites =
+            individual::for_survivors(&sources, &ga.population_penalites, &ga.children_penalties);
+        ga.population_violations =
+            individual::for_survivors(&sources, &ga.population_violations, &children_violations);
+        ga.population = survivors;
+        ga.cache.refresh(&ga.population);
+        self.generation += 1;
+
+        if let Some(diversity) = self.ga.diversity {
+            self.manage_diversity(diversity);
+        }
+        self.record_generation();
+        !self.finished
+    }
+
+    pub fn finish(self) -> GAResult<G> {
+        GAResult {
+            population: self.ga.population,
+            population_eval: self.ga.population_eval,
+            population_penalties: self.ga.population_penalites,
+            statistics: self.ga.statistics,
+            hall_of_fame: self.ga.hall_of_fame,
+        }
     }
 }
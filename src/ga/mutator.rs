@@ -1,8 +1,10 @@
 use super::problem::{InstanceDescription, ProblemSolution, SolutionField};
+use super::random;
+use super::route::SolutionRoutes;
 use rand::{seq::SliceRandom, Rng};
 
-pub trait Mutator {
-    fn mutate(&self, children: &[ProblemSolution]) -> Vec<ProblemSolution>;
+pub trait Mutator<G = ProblemSolution>: Sync {
+    fn mutate(&self, children: &[G]) -> Vec<G>;
 }
 
 pub struct DefaultMutator {
@@ -13,7 +15,7 @@ impl Mutator for DefaultMutator {
     fn mutate(&self, children: &[ProblemSolution]) -> Vec<ProblemSolution> {
         let mut mutated_children: Vec<ProblemSolution> = Vec::new();
 
-        let mut rng = rand::thread_rng();
+        let mut rng = random::rng();
 
         for child in children {
             let should_mutate = rng.gen_bool(self.mutation_rate);
@@ -42,7 +44,7 @@ pub struct InsertionMutator {
 
 impl Mutator for InsertionMutator {
     fn mutate(&self, children: &[ProblemSolution]) -> Vec<ProblemSolution> {
-        let mut rng = rand::thread_rng();
+        let mut rng = random::rng();
 
         let mut mutated = Vec::new();
         // walk over and find indices to mutate
@@ -147,7 +149,7 @@ impl SmartInsertionMutator {
 
 impl Mutator for SmartInsertionMutator {
     fn mutate(&self, children: &[ProblemSolution]) -> Vec<ProblemSolution> {
-        let mut rng = rand::thread_rng();
+        let mut rng = random::rng();
 
         let mut mutated = Vec::new();
         // walk over and find indices to mutate
@@ -190,7 +192,7 @@ pub struct NeighbourSwapAndInsertMutator {
 
 impl Mutator for NeighbourSwapAndInsertMutator {
     fn mutate(&self, children: &[ProblemSolution]) -> Vec<ProblemSolution> {
-        let mut rng = rand::thread_rng();
+        let mut rng = random::rng();
         let mut mutated = Vec::new();
 
         for child in children {
@@ -320,7 +322,7 @@ impl SwapAndInsertMutator {
 
 impl Mutator for SwapAndInsertMutator {
     fn mutate(&self, children: &[ProblemSolution]) -> Vec<ProblemSolution> {
-        let mut rng = rand::thread_rng();
+        let mut rng = random::rng();
         let mut mutated = Vec::new();
 
         for child in children {
@@ -359,3 +361,62 @@ impl Mutator for SwapAndInsertMutator {
         mutated
     }
 }
+
+// Moves a random patient to the position in any route where it costs the least, if that is an
+// improvement. Time warp and overload are weighed against travel time.
+pub struct RelocateMutator {
+    pub mutation_rate: f64,
+    pub time_warp_weight: f64,
+    pub overload_weight: f64,
+    pub problem_instance: InstanceDescription,
+}
+
+impl Mutator for RelocateMutator {
+    fn mutate(&self, children: &[ProblemSolution]) -> Vec<ProblemSolution> {
+        let mut rng = random::rng();
+        let mut mutated = Vec::new();
+
+        for child in children {
+            if !rng.gen_bool(self.mutation_rate) {
+                mutated.push(child.clone());
+                continue;
+            }
+            let mut routes = SolutionRoutes::new(&self.problem_instance, child);
+            let positions: Vec<(usize, usize)> = routes
+                .routes
+                .iter()
+                .enumerate()
+                .flat_map(|(r, route)| (0..route.patients.len()).map(move |i| (r, i)))
+                .collect();
+            let Some(&from) = positions.choose(&mut rng) else {
+                mutated.push(child.clone());
+                continue;
+            };
+
+            let mut best: Option<((usize, usize), f64)> = None;
+            for (r, route) in routes.routes.iter().enumerate() {
+                let len = route.patients.len() - usize::from(r == from.0);
+                for i in 0..=len {
+                    if (r, i) == from {
+                        continue;
+                    }
+                    let delta = routes.relocate_delta(&self.problem_instance, from, (r, i));
+                    let cost = delta.distance
+                        + self.time_warp_weight * delta.time_warp
+                        + self.overload_weight * delta.overload;
+                    if best.is_none_or(|(_, best_cost)| cost < best_cost) {
+                        best = Some(((r, i), cost));
+                    }
+                }
+            }
+            if let Some((to, cost)) = best {
+                if cost < 0. {
+                    routes.relocate(&self.problem_instance, from, to);
+                }
+            }
+            mutated.push(routes.to_solution());
+        }
+
+        mutated
+    }
+}
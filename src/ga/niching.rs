@@ -0,0 +1,79 @@
+use ordered_float::NotNan;
+
+// Adjusts the eval the fitness and penalty functions give together by how crowded the
+// neighbourhood of each individual is, so that selection spreads over several niches instead of
+// converging on one. Neighbourhoods are measured with the GA's distance metric, distances is the
+// distance matrix of the population.
+pub trait Niching: Sync {
+    fn adjust(&self, distances: &[Vec<f64>], population_eval: &[f64]) -> Vec<f64>;
+}
+
+// Goldberg and Richardson's fitness sharing. Every individual within the radius counts towards
+// the niche of another, weighted by 1 - (distance / radius)^alpha, and the eval is degraded by the
+// size of the niche. Evals are usually negative here, so those are multiplied by it rather than
+// divided.
+pub struct FitnessSharing {
+    pub radius: f64,
+    pub alpha: f64,
+}
+
+impl Niching for FitnessSharing {
+    fn adjust(&self, distances: &[Vec<f64>], population_eval: &[f64]) -> Vec<f64> {
+        distances
+            .iter()
+            .zip(population_eval)
+            .map(|(distances, &eval)| {
+                let niche_count: f64 = distances
+                    .iter()
+                    .filter(|&&distance| distance < self.radius)
+                    .map(|distance| 1. - (distance / self.radius).powf(self.alpha))
+                    .sum();
+                if eval >= 0. {
+                    eval / niche_count
+                } else {
+                    eval * niche_count
+                }
+            })
+            .collect()
+    }
+}
+
+// Petrowski's clearing. Going from the best individual down, each one that is still left keeps
+// its eval and becomes the winner of its niche, along with the next best up to the capacity.
+// Everyone else within the radius of a winner is cleared to the worst eval in the population.
+pub struct Clearing {
+    pub radius: f64,
+    pub capacity: usize,
+}
+
+impl Niching for Clearing {
+    fn adjust(&self, distances: &[Vec<f64>], population_eval: &[f64]) -> Vec<f64> {
+        let eval = |i: usize| NotNan::new(population_eval[i]).unwrap();
+        let mut order: Vec<usize> = (0..population_eval.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(eval(i)));
+        let Some(worst) = order.last().map(|&i| population_eval[i]) else {
+            return Vec::new();
+        };
+
+        let mut adjusted = population_eval.to_vec();
+        let mut cleared = vec![false; population_eval.len()];
+        for (position, &winner) in order.iter().enumerate() {
+            if cleared[winner] {
+                continue;
+            }
+            let mut winners = 1;
+            for &other in &order[position + 1..] {
+                if cleared[other] || distances[winner][other] >= self.radius {
+                    continue;
+                }
+                if winners < self.capacity {
+                    winners += 1;
+                } else {
+                    cleared[other] = true;
+                    adjusted[other] = worst;
+                }
+            }
+        }
+        adjusted
+    }
+}
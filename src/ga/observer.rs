@@ -0,0 +1,67 @@
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use ordered_float::NotNan;
+
+use super::problem::ProblemSolution;
+
+pub struct GenerationReport<'a, G = ProblemSolution> {
+    pub generation: usize,
+    pub elapsed: Duration,
+    pub population: &'a [G],
+    pub population_eval: &'a [f64],
+    pub population_penalties: &'a [f64],
+    pub best_fitness: f64,
+    pub best_solution: &'a G,
+}
+
+// Hooks into a GA run. Observers only get shared references, so the ones keeping state need
+// interior mutability. Returning Break from on_generation stops the run after that generation.
+pub trait GenerationObserver<G = ProblemSolution>: Sync {
+    fn on_start(&self, _population: &[G]) {}
+
+    fn on_generation(&self, _report: &GenerationReport<G>) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_new_best(&self, _report: &GenerationReport<G>) {}
+
+    fn on_end(&self, _report: &GenerationReport<G>) {}
+}
+
+// Prints a summary of the population every interval generations
+pub struct ConsoleObserver {
+    pub interval: usize,
+}
+
+impl<G> GenerationObserver<G> for ConsoleObserver {
+    fn on_generation(&self, report: &GenerationReport<G>) -> ControlFlow<()> {
+        if report.generation.is_multiple_of(self.interval) {
+            let average_fitnesses: f64 =
+                report.population_eval.iter().sum::<f64>() / report.population_eval.len() as f64;
+            let average_penalty: f64 = report.population_penalties.iter().sum::<f64>()
+                / report.population_penalties.len() as f64;
+            let max_fitness: f64 = report
+                .population_eval
+                .iter()
+                .copied()
+                .flat_map(NotNan::new)
+                .max()
+                .map(NotNan::into_inner)
+                .unwrap();
+            let best_penalty: f64 = report
+                .population_penalties
+                .iter()
+                .copied()
+                .flat_map(NotNan::new)
+                .max()
+                .map(NotNan::into_inner)
+                .unwrap();
+            println!(
+                "Gen: {}, average fitness: {:.2}, max fitness: {:.2}, average penalty: {:.2}, best_penalty: {:.2}",
+                report.generation, average_fitnesses, max_fitness, average_penalty, best_penalty
+            );
+        }
+        ControlFlow::Continue(())
+    }
+}
@@ -1,24 +1,44 @@
+use super::penalty_function::deb_key;
 use super::problem;
+use super::random;
 use ordered_float::NotNan;
 use rand::distributions::WeightedIndex;
 use rand::prelude::*;
+use rand::seq::index;
 
-pub trait ParentSelector {
+// Picks amount parents from the candidates, in the pairs crossover will mate
+pub trait ParentSelector<G = problem::ProblemSolution>: Sync {
     fn select_parents(
         &self,
-        candidates: &[problem::ProblemSolution],
+        candidates: &[G],
         candidate_fitnesses: &[f64],
-    ) -> Vec<problem::ProblemSolution>;
+        amount: usize,
+    ) -> Vec<G>;
+
+    // Selectors that need to tell the objective from the penalty override this and return Some,
+    // the others are called through select_parents. The evals are those of the fitness and
+    // penalty functions, which include the penalty, and not the ones niching or biased fitness
+    // adjusted, as those no longer hold the objective.
+    fn select_parents_with_penalties(
+        &self,
+        _candidates: &[G],
+        _candidate_evals: &[f64],
+        _candidate_penalties: &[f64],
+        _amount: usize,
+    ) -> Option<Vec<G>> {
+        None
+    }
 }
 
 pub struct DefaultParentSelector {}
 
-impl ParentSelector for DefaultParentSelector {
+impl<G: Clone> ParentSelector<G> for DefaultParentSelector {
     fn select_parents(
         &self,
-        candidates: &[problem::ProblemSolution],
+        candidates: &[G],
         candidate_fitnesses: &[f64],
-    ) -> Vec<problem::ProblemSolution> {
+        amount: usize,
+    ) -> Vec<G> {
         // TODO: Something is fucky with this function; May not do anything at all actually...
         let min_fitness = candidate_fitnesses
             .iter()
@@ -46,10 +66,8 @@ impl ParentSelector for DefaultParentSelector {
         }
         // eprintln!("scaled_fitnesses = {:#?}", scaled_fitnesses);
         let dist = WeightedIndex::new(&scaled_fitnesses).unwrap();
-        let mut rng = thread_rng();
-        let indices: Vec<usize> = (0..candidates.len())
-            .map(|_| dist.sample(&mut rng))
-            .collect();
+        let mut rng = random::rng();
+        let indices: Vec<usize> = (0..amount).map(|_| dist.sample(&mut rng)).collect();
 
         let mut chosen_parents = Vec::new();
         for i in indices {
@@ -59,14 +77,321 @@ impl ParentSelector for DefaultParentSelector {
     }
 }
 
+// Every candidate in order, or a random few of them if fewer parents are needed
 pub struct IdentityParentSelector;
 
-impl ParentSelector for IdentityParentSelector {
+impl<G: Clone> ParentSelector<G> for IdentityParentSelector {
+    fn select_parents(&self, candidates: &[G], _: &[f64], amount: usize) -> Vec<G> {
+        if amount >= candidates.len() {
+            return candidates.iter().cycle().take(amount).cloned().collect();
+        }
+        index::sample(&mut random::rng(), candidates.len(), amount)
+            .into_iter()
+            .map(|i| candidates[i].clone())
+            .collect()
+    }
+}
+
+// Indices of the candidates from worst to best
+fn ranked(candidate_fitnesses: &[f64]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..candidate_fitnesses.len()).collect();
+    indices.sort_by_key(|&i| NotNan::new(candidate_fitnesses[i]).unwrap());
+    indices
+}
+
+fn sample_weighted<G: Clone>(candidates: &[G], weights: &[f64], amount: usize) -> Vec<G> {
+    let dist = WeightedIndex::new(weights).unwrap();
+    let mut rng = random::rng();
+    (0..amount)
+        .map(|_| candidates[dist.sample(&mut rng)].clone())
+        .collect()
+}
+
+// Each parent is the fittest of tournament_size candidates drawn with replacement
+pub struct TournamentParentSelector {
+    tournament_size: usize,
+}
+
+impl TournamentParentSelector {
+    pub fn new(tournament_size: usize) -> TournamentParentSelector {
+        assert!(
+            tournament_size > 0,
+            "a tournament needs at least one candidate"
+        );
+        TournamentParentSelector { tournament_size }
+    }
+}
+
+impl<G: Clone> ParentSelector<G> for TournamentParentSelector {
+    fn select_parents(
+        &self,
+        candidates: &[G],
+        candidate_fitnesses: &[f64],
+        amount: usize,
+    ) -> Vec<G> {
+        let mut rng = random::rng();
+        (0..amount)
+            .map(|_| {
+                let winner = (0..self.tournament_size)
+                    .map(|_| rng.gen_range(0..candidates.len()))
+                    .max_by_key(|&i| NotNan::new(candidate_fitnesses[i]).unwrap())
+                    .unwrap();
+                candidates[winner].clone()
+            })
+            .collect()
+    }
+}
+
+// Selection probability grows linearly with rank, the best candidate is expected to be picked
+// selection_pressure times and the worst 2 - selection_pressure times, so it lies in [1, 2]
+pub struct LinearRankSelector {
+    selection_pressure: f64,
+}
+
+impl LinearRankSelector {
+    pub fn new(selection_pressure: f64) -> LinearRankSelector {
+        assert!(
+            (1. ..=2.).contains(&selection_pressure),
+            "the selection pressure must lie in [1, 2], got {}",
+            selection_pressure
+        );
+        LinearRankSelector { selection_pressure }
+    }
+}
+
+impl<G: Clone> ParentSelector<G> for LinearRankSelector {
+    fn select_parents(
+        &self,
+        candidates: &[G],
+        candidate_fitnesses: &[f64],
+        amount: usize,
+    ) -> Vec<G> {
+        let n = candidates.len() as f64;
+        let mut weights = vec![0.; candidates.len()];
+        for (rank, index) in ranked(candidate_fitnesses).into_iter().enumerate() {
+            weights[index] = if n > 1. {
+                (2. - self.selection_pressure)
+                    + 2. * (self.selection_pressure - 1.) * rank as f64 / (n - 1.)
+            } else {
+                1.
+            };
+        }
+        // The worst candidate has weight 0 with a pressure of 2
+        if weights.iter().all(|&weight| weight == 0.) {
+            weights.fill(1.);
+        }
+        sample_weighted(candidates, &weights, amount)
+    }
+}
+
+// The weight of each candidate is base times that of the next better one, base is in (0, 1)
+pub struct ExponentialRankSelector {
+    pub base: f64,
+}
+
+impl<G: Clone> ParentSelector<G> for ExponentialRankSelector {
+    fn select_parents(
+        &self,
+        candidates: &[G],
+        candidate_fitnesses: &[f64],
+        amount: usize,
+    ) -> Vec<G> {
+        let mut weights = vec![0.; candidates.len()];
+        for (rank_from_best, index) in ranked(candidate_fitnesses).into_iter().rev().enumerate() {
+            weights[index] = self.base.powi(rank_from_best as i32);
+        }
+        sample_weighted(candidates, &weights, amount)
+    }
+}
+
+// Picks all parents with one spin of a wheel with evenly spaced pointers, which keeps the number
+// of copies of each candidate close to its expected value. The wheel is weighted by how much a
+// candidate beats the worst one, so it works for negative fitnesses.
+pub struct StochasticUniversalSampling;
+
+impl<G: Clone> ParentSelector<G> for StochasticUniversalSampling {
+    fn select_parents(
+        &self,
+        candidates: &[G],
+        candidate_fitnesses: &[f64],
+        amount: usize,
+    ) -> Vec<G> {
+        let min_fitness = candidate_fitnesses
+            .iter()
+            .copied()
+            .flat_map(NotNan::new)
+            .min()
+            .map(NotNan::into_inner)
+            .unwrap();
+        let mut weights: Vec<f64> = candidate_fitnesses
+            .iter()
+            .map(|&fitness| fitness - min_fitness)
+            .collect();
+        if weights.iter().all(|&weight| weight == 0.) {
+            weights.fill(1.);
+        }
+
+        let total: f64 = weights.iter().sum();
+        let spacing = total / amount as f64;
+        let mut pointer = random::rng().gen_range(0.0..spacing);
+        let mut chosen_parents = Vec::with_capacity(amount);
+        let mut cumulative = 0.;
+        for (candidate, weight) in candidates.iter().zip(weights) {
+            cumulative += weight;
+            while pointer < cumulative && chosen_parents.len() < amount {
+                chosen_parents.push(candidate.clone());
+                pointer += spacing;
+            }
+        }
+        // Rounding can leave the last pointer just past the end of the wheel
+        while chosen_parents.len() < amount {
+            chosen_parents.push(candidates[candidates.len() - 1].clone());
+        }
+        chosen_parents.shuffle(&mut random::rng());
+        chosen_parents
+    }
+}
+
+// Weights candidates by exp(fitness / temperature), a high temperature selects almost uniformly
+// and a low one almost only the best. A temperature of 0 or below only selects the best.
+pub struct BoltzmannSelector {
+    pub temperature: f64,
+}
+
+impl<G: Clone> ParentSelector<G> for BoltzmannSelector {
+    fn select_parents(
+        &self,
+        candidates: &[G],
+        candidate_fitnesses: &[f64],
+        amount: usize,
+    ) -> Vec<G> {
+        let (best, max_fitness) = candidate_fitnesses
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by_key(|&(_, fitness)| NotNan::new(fitness).unwrap())
+            .unwrap();
+        if self.temperature <= 0. {
+            return vec![candidates[best].clone(); amount];
+        }
+        // Shifting by the best fitness keeps exp from overflowing without changing the proportions
+        let weights: Vec<f64> = candidate_fitnesses
+            .iter()
+            .map(|&fitness| ((fitness - max_fitness) / self.temperature).exp())
+            .collect();
+        sample_weighted(candidates, &weights, amount)
+    }
+}
+
+// Tournament selection by Deb's rules, so a short but infeasible individual cannot beat a
+// feasible one however the penalty multipliers are set
+pub struct ConstrainedTournamentParentSelector {
+    tournament_size: usize,
+}
+
+impl ConstrainedTournamentParentSelector {
+    pub fn new(tournament_size: usize) -> ConstrainedTournamentParentSelector {
+        assert!(
+            tournament_size > 0,
+            "a tournament needs at least one candidate"
+        );
+        ConstrainedTournamentParentSelector { tournament_size }
+    }
+
+    fn tournaments<G: Clone>(
+        &self,
+        candidates: &[G],
+        candidate_evals: &[f64],
+        candidate_penalties: &[f64],
+        amount: usize,
+    ) -> Vec<G> {
+        let mut rng = random::rng();
+        (0..amount)
+            .map(|_| {
+                let winner = (0..self.tournament_size)
+                    .map(|_| rng.gen_range(0..candidates.len()))
+                    .max_by_key(|&i| deb_key(candidate_evals[i], candidate_penalties[i]))
+                    .unwrap();
+                candidates[winner].clone()
+            })
+            .collect()
+    }
+}
+
+impl<G: Clone> ParentSelector<G> for ConstrainedTournamentParentSelector {
     fn select_parents(
         &self,
-        candidates: &[problem::ProblemSolution],
-        _: &[f64],
-    ) -> Vec<problem::ProblemSolution> {
-        Vec::from(candidates)
+        candidates: &[G],
+        candidate_fitnesses: &[f64],
+        amount: usize,
+    ) -> Vec<G> {
+        let no_penalties = vec![0.; candidates.len()];
+        self.tournaments(candidates, candidate_fitnesses, &no_penalties, amount)
+    }
+
+    fn select_parents_with_penalties(
+        &self,
+        candidates: &[G],
+        candidate_evals: &[f64],
+        candidate_penalties: &[f64],
+        amount: usize,
+    ) -> Option<Vec<G>> {
+        Some(self.tournaments(candidates, candidate_evals, candidate_penalties, amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+
+    fn count(parents: &[usize], candidate: usize) -> usize {
+        parents
+            .iter()
+            .filter(|&&parent| parent == candidate)
+            .count()
+    }
+
+    #[test]
+    fn linear_rank_favours_better_ranks() {
+        let selector = LinearRankSelector::new(2.);
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let parents = random::scoped(&mut rng, || {
+            selector.select_parents(&[0, 1, 2], &[-3., -1., -2.], 3000)
+        });
+        // Weights 0, 1 and 2 by rank, whatever the evals are
+        assert_eq!(count(&parents, 0), 0);
+        assert!((1900..2100).contains(&count(&parents, 1)));
+        assert!((900..1100).contains(&count(&parents, 2)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn linear_rank_rejects_pressure_above_two() {
+        LinearRankSelector::new(2.5);
+    }
+
+    #[test]
+    fn stochastic_universal_sampling_gives_the_expected_copies() {
+        // The wheel is weighted 0, 1 and 3 by how much each beats the worst, so four evenly spaced
+        // pointers land once on the second candidate and three times on the third wherever the
+        // spin starts
+        for seed in 0..20 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let parents = random::scoped(&mut rng, || {
+                StochasticUniversalSampling.select_parents(&[0, 1, 2], &[-3., -2., 0.], 4)
+            });
+            assert_eq!(count(&parents, 0), 0);
+            assert_eq!(count(&parents, 1), 1);
+            assert_eq!(count(&parents, 2), 3);
+        }
+    }
+
+    #[test]
+    fn boltzmann_without_temperature_selects_the_best() {
+        let selector = BoltzmannSelector { temperature: 0. };
+        let parents = selector.select_parents(&[1, 2, 3], &[-3., -1., -2.], 3);
+        assert_eq!(parents, vec![2, 2, 2]);
     }
 }
@@ -1,11 +1,49 @@
+use ordered_float::NotNan;
+use serde::{Deserialize, Serialize};
+
 use super::problem::{InstanceDescription, ProblemSolution, SolutionField};
 
-pub trait PenaltyFunction {
-    fn get_penalties(
-        &self,
-        solutions: &[ProblemSolution],
-        instance: &InstanceDescription,
-    ) -> Vec<f64>;
+// The penalty split up by the constraint that was broken, every part is zero or negative
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Violations {
+    pub overcapacity: f64,
+    pub missed_windows: f64,
+    pub late_depot: f64,
+}
+
+impl Violations {
+    pub fn total(&self) -> f64 {
+        self.overcapacity + self.missed_windows + self.late_depot
+    }
+}
+
+// Sort key for Deb's rules, where a larger key is better: feasible individuals beat infeasible
+// ones, feasible ones are compared by objective and infeasible ones by how much they violate the
+// constraints, then by objective. eval is the fitness with the penalty included.
+pub fn deb_key(eval: f64, penalty: f64) -> (bool, NotNan<f64>, NotNan<f64>) {
+    let feasible = penalty == 0.;
+    let objective = NotNan::new(eval - penalty).unwrap();
+    let primary = if feasible {
+        objective
+    } else {
+        NotNan::new(penalty).unwrap()
+    };
+    (feasible, primary, objective)
+}
+
+pub trait PenaltyFunction<G = ProblemSolution, P = InstanceDescription>: Sync {
+    fn get_penalty(&self, solution: &G, instance: &P) -> f64;
+
+    // Names of the constraints the penalty is split up by, one statistics column each
+    fn violation_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // The penalty together with its breakdown for the statistics, in the order of
+    // violation_names. Penalties that are not split up by constraint leave the breakdown empty.
+    fn get_penalty_and_violations(&self, solution: &G, instance: &P) -> (f64, Vec<f64>) {
+        (self.get_penalty(solution, instance), Vec::new())
+    }
 }
 
 pub struct DefaultPenalty {
@@ -15,85 +53,110 @@ pub struct DefaultPenalty {
 }
 
 impl PenaltyFunction for DefaultPenalty {
-    fn get_penalties(
+    fn get_penalty(&self, solution: &ProblemSolution, instance: &InstanceDescription) -> f64 {
+        self.get_violations(solution, instance).total()
+    }
+
+    fn violation_names(&self) -> Vec<String> {
+        ["overcapacity", "missed_windows", "late_depot"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    fn get_penalty_and_violations(
         &self,
-        solutions: &[ProblemSolution],
+        solution: &ProblemSolution,
         instance: &InstanceDescription,
-    ) -> Vec<f64> {
-        let mut penalties = Vec::new();
-        for solution in solutions {
-            // Calculate overcapacity
-            let mut total_overcapacity = 0.;
-            let mut current_demand = 0.;
-            for solution_token in &solution.0 {
-                match solution_token {
-                    SolutionField::Patient(id) => {
-                        current_demand += instance.patients[*id].demand as f64;
-                    }
-                    SolutionField::Separator(_) => {
-                        total_overcapacity +=
-                            (instance.capacity_nurse as f64 - current_demand).min(0.);
-                        current_demand = 0.;
-                    }
+    ) -> (f64, Vec<f64>) {
+        let violations = self.get_violations(solution, instance);
+        (
+            violations.total(),
+            vec![
+                violations.overcapacity,
+                violations.missed_windows,
+                violations.late_depot,
+            ],
+        )
+    }
+}
+
+impl DefaultPenalty {
+    pub fn get_violations(
+        &self,
+        solution: &ProblemSolution,
+        instance: &InstanceDescription,
+    ) -> Violations {
+        // Calculate overcapacity
+        let mut total_overcapacity = 0.;
+        let mut current_demand = 0.;
+        for solution_token in &solution.0 {
+            match solution_token {
+                SolutionField::Patient(id) => {
+                    current_demand += instance.patients[*id].demand as f64;
+                }
+                SolutionField::Separator(_) => {
+                    total_overcapacity += (instance.capacity_nurse as f64 - current_demand).min(0.);
+                    current_demand = 0.;
                 }
             }
-            let overcapacity_penalty = total_overcapacity * self.overcapacity_multiplier;
+        }
+        let overcapacity_penalty = total_overcapacity * self.overcapacity_multiplier;
 
-            // Calculate missed windows
-            let mut total_missed_time = 0.;
-            let mut total_late_depot_times = 0.;
+        // Calculate missed windows
+        let mut total_missed_time = 0.;
+        let mut total_late_depot_times = 0.;
 
-            let mut current_time = 0.;
-            let mut previous_patient_id: Option<usize> = None;
-            for solution_token in &solution.0 {
-                match solution_token {
-                    SolutionField::Patient(id) => {
-                        if let Some(previous_id) = previous_patient_id {
-                            // Treat previous patient
-                            let previous_patient = instance.patients[previous_id];
-                            let care_time = previous_patient.care_time as f64;
-                            current_time += care_time;
-                            current_time =
-                                current_time.max(previous_patient.start_time as f64 + care_time);
-                            let leftover_time = previous_patient.end_time as f64 - current_time;
-                            total_missed_time += leftover_time.min(0.);
-                            // Travel to next patient
-                            current_time += instance.travel_time_patient(previous_id, *id);
-                        } else {
-                            // travel from depot to first spot
-                            current_time += instance.travel_time_depot(*id);
-                        }
-                        previous_patient_id = Some(*id);
+        let mut current_time = 0.;
+        let mut previous_patient_id: Option<usize> = None;
+        for solution_token in &solution.0 {
+            match solution_token {
+                SolutionField::Patient(id) => {
+                    if let Some(previous_id) = previous_patient_id {
+                        // Treat previous patient
+                        let previous_patient = instance.patients[previous_id];
+                        let care_time = previous_patient.care_time as f64;
+                        current_time += care_time;
+                        current_time =
+                            current_time.max(previous_patient.start_time as f64 + care_time);
+                        let leftover_time = previous_patient.end_time as f64 - current_time;
+                        total_missed_time += leftover_time.min(0.);
+                        // Travel to next patient
+                        current_time += instance.travel_time_patient(previous_id, *id);
+                    } else {
+                        // travel from depot to first spot
+                        current_time += instance.travel_time_depot(*id);
                     }
-                    SolutionField::Separator(_) => {
-                        if let Some(previous_id) = previous_patient_id {
-                            // Treat last patient, then go back to depot
-                            let last_patient = instance.patients[previous_id];
-                            let care_time = last_patient.care_time as f64;
-                            current_time += care_time;
-                            current_time =
-                                current_time.max(last_patient.start_time as f64 + care_time);
-                            let leftover_time = last_patient.end_time as f64 - current_time;
-                            total_missed_time += leftover_time.min(0.);
-                            // Calculate missed depot time
-                            current_time += instance.travel_time_depot(previous_id);
-                            total_late_depot_times +=
-                                (instance.depot.return_time as f64 - current_time).min(0.);
-                            // Also reset variables
-                            current_time = 0.;
-                            previous_patient_id = None;
-                        } else {
-                            // Empty route, NOOP should be fine here
-                        }
+                    previous_patient_id = Some(*id);
+                }
+                SolutionField::Separator(_) => {
+                    if let Some(previous_id) = previous_patient_id {
+                        // Treat last patient, then go back to depot
+                        let last_patient = instance.patients[previous_id];
+                        let care_time = last_patient.care_time as f64;
+                        current_time += care_time;
+                        current_time = current_time.max(last_patient.start_time as f64 + care_time);
+                        let leftover_time = last_patient.end_time as f64 - current_time;
+                        total_missed_time += leftover_time.min(0.);
+                        // Calculate missed depot time
+                        current_time += instance.travel_time_depot(previous_id);
+                        total_late_depot_times +=
+                            (instance.depot.return_time as f64 - current_time).min(0.);
+                        // Also reset variables
+                        current_time = 0.;
+                        previous_patient_id = None;
+                    } else {
+                        // Empty route, NOOP should be fine here
                     }
                 }
             }
-            let missed_windows_penalty = total_missed_time * self.missed_window_multiplier;
-            let late_depot_penalty = total_late_depot_times * self.late_depot_multiplier;
-
-            penalties.push(overcapacity_penalty + missed_windows_penalty + late_depot_penalty);
         }
+        let missed_windows_penalty = total_missed_time * self.missed_window_multiplier;
+        let late_depot_penalty = total_late_depot_times * self.late_depot_multiplier;
 
-        penalties
+        Violations {
+            overcapacity: overcapacity_penalty,
+            missed_windows: missed_windows_penalty,
+            late_depot: late_depot_penalty,
+        }
     }
 }
@@ -1,6 +1,11 @@
-use std::{collections::HashMap, fs::File, io::BufReader, path::Path};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct InstanceDescription {
@@ -21,6 +26,68 @@ impl InstanceDescription {
     pub fn travel_time_depot(&self, patient: usize) -> f64 {
         self.travel_times[0][patient]
     }
+
+    // Travel time between two stops of a route, where None is the depot
+    pub fn travel_time(&self, from: Option<usize>, to: Option<usize>) -> f64 {
+        match (from, to) {
+            (Some(from), Some(to)) => self.travel_time_patient(from, to),
+            (Some(patient), None) | (None, Some(patient)) => self.travel_time_depot(patient),
+            (None, None) => 0.,
+        }
+    }
+
+    // Walks a single nurse route with the same timing rules as the penalty function
+    pub fn route_schedule(&self, route: &[usize]) -> RouteSchedule {
+        let mut schedule = RouteSchedule {
+            service_starts: Vec::with_capacity(route.len()),
+            ..Default::default()
+        };
+        let mut current_time: f64 = 0.;
+        let mut previous_patient_id: Option<usize> = None;
+        for &id in route {
+            let patient = self.patients[id];
+            let travel_time = if let Some(previous_id) = previous_patient_id {
+                self.travel_time_patient(previous_id, id)
+            } else {
+                self.travel_time_depot(id)
+            };
+            schedule.travel_time += travel_time;
+            current_time += travel_time;
+            schedule
+                .service_starts
+                .push(current_time.max(patient.start_time as f64));
+
+            let care_time = patient.care_time as f64;
+            current_time += care_time;
+            current_time = current_time.max(patient.start_time as f64 + care_time);
+            schedule.missed_time += (current_time - patient.end_time as f64).max(0.);
+            schedule.demand += patient.demand;
+            previous_patient_id = Some(id);
+        }
+        if let Some(previous_id) = previous_patient_id {
+            schedule.travel_time += self.travel_time_depot(previous_id);
+            current_time += self.travel_time_depot(previous_id);
+        }
+        schedule.depot_arrival = current_time;
+        schedule
+    }
+
+    pub fn route_is_feasible(&self, route: &[usize]) -> bool {
+        let schedule = self.route_schedule(route);
+        schedule.demand <= self.capacity_nurse
+            && schedule.missed_time == 0.
+            && schedule.depot_arrival <= self.depot.return_time as f64
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RouteSchedule {
+    pub demand: usize,
+    pub travel_time: f64,
+    pub missed_time: f64,
+    pub depot_arrival: f64,
+    // When the care of each patient in the route can begin, waiting for the window if early
+    pub service_starts: Vec<f64>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -91,6 +158,52 @@ impl Patient {
 #[derive(Debug)]
 pub struct Phenotype(pub Vec<Vec<usize>>);
 
+impl Phenotype {
+    // Reads a solution either in the text format written to problem_solutions or as a JSON list
+    // of routes. Both number patients from 1 like the instance files.
+    pub fn from_file<P>(path: P, nurses: usize) -> Phenotype
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).unwrap();
+        let mut routes: Vec<Vec<usize>> = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::from_str(&contents).unwrap()
+        } else {
+            contents
+                .lines()
+                .filter(|line| line.starts_with("Nurse"))
+                .map(|line| {
+                    // Nurse  1    81.9    83   D(0) ->   75 (36.1, 67.0) [ 57,  182] -> ...
+                    line.split("->")
+                        .skip(1)
+                        .map(str::trim)
+                        .filter(|part| !part.is_empty() && !part.starts_with('D'))
+                        .map(|part| part.split_whitespace().next().unwrap().parse().unwrap())
+                        .collect()
+                })
+                .collect()
+        };
+        for route in routes.iter_mut() {
+            for id in route.iter_mut() {
+                *id -= 1;
+            }
+        }
+        assert!(
+            routes.len() <= nurses,
+            "{} has {} routes, but there are only {} nurses",
+            path.display(),
+            routes.len(),
+            nurses
+        );
+        routes.resize(nurses, Vec::new());
+        Phenotype(routes)
+    }
+}
+
 impl Into<ProblemSolution> for Phenotype {
     fn into(self) -> ProblemSolution {
         let mut solution = Vec::new();
@@ -106,13 +219,13 @@ impl Into<ProblemSolution> for Phenotype {
     }
 }
 
-#[derive(Debug, Clone, Copy, is_enum_variant, Eq, PartialEq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, is_enum_variant, Eq, PartialEq, Hash)]
 pub enum SolutionField {
     Patient(usize),
     Separator(usize),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ProblemSolution(pub Vec<SolutionField>);
 
 enum Relation {
@@ -0,0 +1,42 @@
+use std::cell::RefCell;
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+// Operators draw their randomness from a thread local generator instead of rand::thread_rng, so
+// that the GA can install its own seeded generator while they run and save it in checkpoints.
+thread_local! {
+    static RNG: RefCell<ChaCha8Rng> = RefCell::new(ChaCha8Rng::from_entropy());
+}
+
+pub struct GARng;
+
+pub fn rng() -> GARng {
+    GARng
+}
+
+impl RngCore for GARng {
+    fn next_u32(&mut self) -> u32 {
+        RNG.with(|rng| rng.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RNG.with(|rng| rng.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        RNG.with(|rng| rng.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        RNG.with(|rng| rng.borrow_mut().try_fill_bytes(dest))
+    }
+}
+
+// Runs f with the given generator installed, storing the advanced generator back afterwards
+pub fn scoped<T>(rng: &mut ChaCha8Rng, f: impl FnOnce() -> T) -> T {
+    let previous = RNG.with(|installed| installed.replace(rng.clone()));
+    let result = f();
+    *rng = RNG.with(|installed| installed.replace(previous));
+    result
+}
@@ -1,13 +1,10 @@
 use rand::Rng;
 
 use super::problem::{self, InstanceDescription, SolutionField};
+use super::random;
 
-pub trait RepairMechanism {
-    fn repair(
-        &self,
-        solutions: &[problem::ProblemSolution],
-        instance: &InstanceDescription,
-    ) -> Vec<problem::ProblemSolution>;
+pub trait RepairMechanism<G = problem::ProblemSolution, P = InstanceDescription>: Sync {
+    fn repair(&self, solutions: &[G], instance: &P) -> Vec<G>;
 }
 
 pub struct DefaultRepair;
@@ -19,7 +16,7 @@ impl RepairMechanism for DefaultRepair {
         instance: &InstanceDescription,
     ) -> Vec<problem::ProblemSolution> {
         let mut repaired_solutions = Vec::new();
-        let mut rng = rand::thread_rng();
+        let mut rng = random::rng();
 
         for solution in solutions {
             let mut cloned = solution.clone();
@@ -0,0 +1,433 @@
+use super::problem::{InstanceDescription, ProblemSolution, SolutionField};
+
+// Summary of a sequence of stops that can be concatenated with another in constant time, as in
+// Vidal et al. (2013). Lateness is measured as time warp: arriving after the latest start counts
+// the difference and continues as if on time. A route is feasible exactly when it has no time
+// warp and no overload, but the time warp of an infeasible route differs from the missed time the
+// penalty function counts.
+#[derive(Debug, Clone, Copy)]
+pub struct Segment {
+    first: Option<usize>,
+    last: Option<usize>,
+    pub duration: f64,
+    pub time_warp: f64,
+    // Window for the start of the segment that avoids waiting and time warp where possible
+    pub earliest: f64,
+    pub latest: f64,
+    pub distance: f64,
+    pub load: usize,
+}
+
+impl Segment {
+    pub fn depot(instance: &InstanceDescription) -> Segment {
+        Segment {
+            first: None,
+            last: None,
+            duration: 0.,
+            time_warp: 0.,
+            earliest: 0.,
+            latest: instance.depot.return_time as f64,
+            distance: 0.,
+            load: 0,
+        }
+    }
+
+    pub fn patient(instance: &InstanceDescription, id: usize) -> Segment {
+        let patient = instance.patients[id];
+        Segment {
+            first: Some(id),
+            last: Some(id),
+            duration: patient.care_time as f64,
+            time_warp: 0.,
+            earliest: patient.start_time as f64,
+            latest: patient.end_time as f64 - patient.care_time as f64,
+            distance: 0.,
+            load: patient.demand,
+        }
+    }
+
+    pub fn concat(&self, other: &Segment, instance: &InstanceDescription) -> Segment {
+        let travel = instance.travel_time(self.last, other.first);
+        let delta = self.duration - self.time_warp + travel;
+        let waiting = (other.earliest - delta - self.latest).max(0.);
+        let time_warp = (self.earliest + delta - other.latest).max(0.);
+        Segment {
+            first: self.first,
+            last: other.last,
+            duration: self.duration + other.duration + travel + waiting,
+            time_warp: self.time_warp + other.time_warp + time_warp,
+            earliest: (other.earliest - delta).max(self.earliest) - waiting,
+            latest: (other.latest - delta).min(self.latest) + time_warp,
+            distance: self.distance + other.distance + travel,
+            load: self.load + other.load,
+        }
+    }
+
+    pub fn overload(&self, instance: &InstanceDescription) -> f64 {
+        self.load.saturating_sub(instance.capacity_nurse) as f64
+    }
+
+    // Evaluates a whole route from the depot and back in O(route)
+    pub fn route(instance: &InstanceDescription, route: &[usize]) -> Segment {
+        route
+            .iter()
+            .fold(Segment::depot(instance), |segment, &id| {
+                segment.concat(&Segment::patient(instance, id), instance)
+            })
+            .concat(&Segment::depot(instance), instance)
+    }
+}
+
+// A route with the segments of each prefix and suffix, so moves that cut it somewhere can be
+// evaluated without walking it
+#[derive(Debug, Clone)]
+pub struct RouteData {
+    pub patients: Vec<usize>,
+    // The depot followed by the first k patients
+    forward: Vec<Segment>,
+    // Patient k to the end followed by the depot
+    backward: Vec<Segment>,
+    total: Segment,
+    separator: Option<SolutionField>,
+}
+
+impl RouteData {
+    pub fn new(
+        instance: &InstanceDescription,
+        patients: Vec<usize>,
+        separator: Option<SolutionField>,
+    ) -> RouteData {
+        let mut forward = Vec::with_capacity(patients.len() + 1);
+        forward.push(Segment::depot(instance));
+        for &id in &patients {
+            let next = forward
+                .last()
+                .unwrap()
+                .concat(&Segment::patient(instance, id), instance);
+            forward.push(next);
+        }
+        let mut backward = vec![Segment::depot(instance); patients.len() + 1];
+        for (position, &id) in patients.iter().enumerate().rev() {
+            backward[position] =
+                Segment::patient(instance, id).concat(&backward[position + 1], instance);
+        }
+        let total = forward[0].concat(&backward[0], instance);
+        RouteData {
+            patients,
+            forward,
+            backward,
+            total,
+            separator,
+        }
+    }
+
+    // The whole route from the depot and back
+    pub fn segment(&self) -> Segment {
+        self.total
+    }
+
+    // The route without the patient at the given position
+    fn without(&self, position: usize, instance: &InstanceDescription) -> Segment {
+        self.forward[position].concat(&self.backward[position + 1], instance)
+    }
+
+    // The route with a patient put in before the given position
+    fn with(&self, position: usize, id: usize, instance: &InstanceDescription) -> Segment {
+        self.forward[position]
+            .concat(&Segment::patient(instance, id), instance)
+            .concat(&self.backward[position], instance)
+    }
+
+    // The route with the patient at the given position replaced by another
+    fn replaced(&self, position: usize, id: usize, instance: &InstanceDescription) -> Segment {
+        self.forward[position]
+            .concat(&Segment::patient(instance, id), instance)
+            .concat(&self.backward[position + 1], instance)
+    }
+}
+
+// Change of the route measures caused by a move, negative values are improvements
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MoveDelta {
+    pub distance: f64,
+    pub time_warp: f64,
+    pub overload: f64,
+}
+
+impl MoveDelta {
+    fn between(before: &[Segment], after: &[Segment], instance: &InstanceDescription) -> MoveDelta {
+        let sum = |segments: &[Segment], measure: &dyn Fn(&Segment) -> f64| -> f64 {
+            segments.iter().map(measure).sum()
+        };
+        MoveDelta {
+            distance: sum(after, &|s| s.distance) - sum(before, &|s| s.distance),
+            time_warp: sum(after, &|s| s.time_warp) - sum(before, &|s| s.time_warp),
+            overload: sum(after, &|s| s.overload(instance))
+                - sum(before, &|s| s.overload(instance)),
+        }
+    }
+}
+
+// A solution split into routes for delta evaluation. Positions are (route, index in route).
+// Moves between two routes are evaluated in constant time, moves within one route in O(route).
+#[derive(Debug, Clone)]
+pub struct SolutionRoutes {
+    pub routes: Vec<RouteData>,
+}
+
+impl SolutionRoutes {
+    pub fn new(instance: &InstanceDescription, solution: &ProblemSolution) -> SolutionRoutes {
+        let mut routes = Vec::new();
+        let mut current = Vec::new();
+        for &token in &solution.0 {
+            match token {
+                SolutionField::Patient(id) => current.push(id),
+                SolutionField::Separator(_) => {
+                    routes.push(RouteData::new(
+                        instance,
+                        std::mem::take(&mut current),
+                        Some(token),
+                    ));
+                }
+            }
+        }
+        // Patients after the last separator still form a route
+        if !current.is_empty() {
+            routes.push(RouteData::new(instance, current, None));
+        }
+        SolutionRoutes { routes }
+    }
+
+    pub fn to_solution(&self) -> ProblemSolution {
+        let mut solution = Vec::new();
+        for route in &self.routes {
+            solution.extend(route.patients.iter().map(|&id| SolutionField::Patient(id)));
+            solution.extend(route.separator);
+        }
+        ProblemSolution(solution)
+    }
+
+    pub fn distance(&self) -> f64 {
+        self.routes
+            .iter()
+            .map(|route| route.segment().distance)
+            .sum()
+    }
+
+    // Moves the patient at `from` so that it ends up at `to`, where the index in the target
+    // route counts after the patient has been taken out
+    pub fn relocate_delta(
+        &self,
+        instance: &InstanceDescription,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> MoveDelta {
+        let source = &self.routes[from.0];
+        let id = source.patients[from.1];
+        if from.0 == to.0 {
+            let mut patients = source.patients.clone();
+            patients.remove(from.1);
+            patients.insert(to.1, id);
+            return MoveDelta::between(
+                &[source.segment()],
+                &[Segment::route(instance, &patients)],
+                instance,
+            );
+        }
+        let target = &self.routes[to.0];
+        MoveDelta::between(
+            &[source.segment(), target.segment()],
+            &[
+                source.without(from.1, instance),
+                target.with(to.1, id, instance),
+            ],
+            instance,
+        )
+    }
+
+    pub fn relocate(
+        &mut self,
+        instance: &InstanceDescription,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) {
+        let id = self.routes[from.0].patients[from.1];
+        let mut source = self.routes[from.0].patients.clone();
+        source.remove(from.1);
+        self.update(instance, from.0, source);
+        let mut target = self.routes[to.0].patients.clone();
+        target.insert(to.1, id);
+        self.update(instance, to.0, target);
+    }
+
+    // Exchanges the patients at the two positions
+    pub fn swap_delta(
+        &self,
+        instance: &InstanceDescription,
+        a: (usize, usize),
+        b: (usize, usize),
+    ) -> MoveDelta {
+        let route_a = &self.routes[a.0];
+        let route_b = &self.routes[b.0];
+        let id_a = route_a.patients[a.1];
+        let id_b = route_b.patients[b.1];
+        if a.0 == b.0 {
+            let mut patients = route_a.patients.clone();
+            patients.swap(a.1, b.1);
+            return MoveDelta::between(
+                &[route_a.segment()],
+                &[Segment::route(instance, &patients)],
+                instance,
+            );
+        }
+        MoveDelta::between(
+            &[route_a.segment(), route_b.segment()],
+            &[
+                route_a.replaced(a.1, id_b, instance),
+                route_b.replaced(b.1, id_a, instance),
+            ],
+            instance,
+        )
+    }
+
+    pub fn swap(&mut self, instance: &InstanceDescription, a: (usize, usize), b: (usize, usize)) {
+        let id_a = self.routes[a.0].patients[a.1];
+        let id_b = self.routes[b.0].patients[b.1];
+        let mut patients = self.routes[a.0].patients.clone();
+        patients[a.1] = id_b;
+        self.update(instance, a.0, patients);
+        let mut patients = self.routes[b.0].patients.clone();
+        patients[b.1] = id_a;
+        self.update(instance, b.0, patients);
+    }
+
+    fn update(&mut self, instance: &InstanceDescription, route: usize, patients: Vec<usize>) {
+        let separator = self.routes[route].separator;
+        self.routes[route] = RouteData::new(instance, patients, separator);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::ga::fitness_function::{DefaultFitness, FitnessFunction};
+    use crate::ga::initializer::{
+        DefaultInitializer, I1Seed, PopulationInitializer, SolomonI1Initializer,
+    };
+    use crate::ga::penalty_function::DefaultPenalty;
+    use crate::ga::random;
+
+    // Travel time, overload and whether any patient or depot is reached too late, from the
+    // fitness and penalty functions. Time warp measures lateness differently from the missed
+    // time, so only whether there is any can be compared.
+    fn evaluate(instance: &InstanceDescription, solution: &ProblemSolution) -> (f64, f64, bool) {
+        let penalty = DefaultPenalty {
+            overcapacity_multiplier: 1.,
+            missed_window_multiplier: 1.,
+            late_depot_multiplier: 1.,
+        };
+        let violations = penalty.get_violations(solution, instance);
+        (
+            -DefaultFitness {}.get_fitness(solution, instance),
+            -violations.overcapacity,
+            violations.missed_windows + violations.late_depot < 0.,
+        )
+    }
+
+    fn time_warp(routes: &SolutionRoutes) -> f64 {
+        routes
+            .routes
+            .iter()
+            .map(|route| route.segment().time_warp)
+            .sum()
+    }
+
+    // Checks a move's delta against evaluating the solution from scratch after the move
+    fn check(
+        instance: &InstanceDescription,
+        before: &SolutionRoutes,
+        after: &SolutionRoutes,
+        delta: MoveDelta,
+    ) {
+        let (distance_before, overload_before, _) = evaluate(instance, &before.to_solution());
+        let (distance_after, overload_after, late) = evaluate(instance, &after.to_solution());
+        assert!((distance_after - distance_before - delta.distance).abs() < 1e-6);
+        assert_eq!(overload_after - overload_before, delta.overload);
+        let time_warp_after = time_warp(before) + delta.time_warp;
+        assert!((time_warp(after) - time_warp_after).abs() < 1e-6);
+        assert_eq!(time_warp_after > 1e-6, late);
+    }
+
+    fn random_position(rng: &mut ChaCha8Rng, routes: &SolutionRoutes) -> Option<(usize, usize)> {
+        let route = rng.gen_range(0..routes.routes.len());
+        let patients = routes.routes[route].patients.len();
+        (patients > 0).then(|| (route, rng.gen_range(0..patients)))
+    }
+
+    #[test]
+    fn deltas_match_a_full_evaluation() {
+        let instance = InstanceDescription::from_file("test/test_0.json");
+        // Constructed solutions are mostly feasible and random ones are not, so moves go both ways
+        let constructed = SolomonI1Initializer {
+            initial_population: 10,
+            problem_instance: instance.clone(),
+            alpha_range: 0.0..=1.0,
+            mu_range: 0.5..=1.5,
+            lambda_range: 1.0..=2.0,
+            seed_criteria: vec![I1Seed::FarthestPatient, I1Seed::EarliestDeadline],
+        };
+        let random = DefaultInitializer {
+            initial_population: 10,
+            patient_amount: instance.patients.len(),
+            nurses: instance.nbr_nurses,
+        };
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let solutions = random::scoped(&mut rng, || {
+            [
+                constructed.initialize_population(),
+                random.initialize_population(),
+            ]
+            .concat()
+        });
+
+        let mut moves = 0;
+        for solution in &solutions {
+            let routes = SolutionRoutes::new(&instance, solution);
+            assert_eq!(&routes.to_solution(), solution);
+            for _ in 0..200 {
+                if let Some(from) = random_position(&mut rng, &routes) {
+                    let route = rng.gen_range(0..routes.routes.len());
+                    let length = routes.routes[route].patients.len() - usize::from(route == from.0);
+                    let to = (route, rng.gen_range(0..=length));
+                    let mut relocated = routes.clone();
+                    relocated.relocate(&instance, from, to);
+                    check(
+                        &instance,
+                        &routes,
+                        &relocated,
+                        routes.relocate_delta(&instance, from, to),
+                    );
+                    moves += 1;
+                }
+                if let (Some(a), Some(b)) = (
+                    random_position(&mut rng, &routes),
+                    random_position(&mut rng, &routes),
+                ) {
+                    let mut swapped = routes.clone();
+                    swapped.swap(&instance, a, b);
+                    check(
+                        &instance,
+                        &routes,
+                        &swapped,
+                        routes.swap_delta(&instance, a, b),
+                    );
+                    moves += 1;
+                }
+            }
+        }
+        assert!(moves > 1000);
+    }
+}
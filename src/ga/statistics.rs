@@ -0,0 +1,156 @@
+use std::{fmt::Write, fs, path::Path};
+
+use ordered_float::NotNan;
+use serde::{Deserialize, Serialize};
+
+use super::distance::DistanceMetric;
+use super::Genome;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GenerationStatistics {
+    pub generation: usize,
+    pub elapsed_seconds: f64,
+    pub best_fitness: f64,
+    pub mean_fitness: f64,
+    pub worst_fitness: f64,
+    pub best_penalty: f64,
+    pub mean_penalty: f64,
+    // Mean penalty from each kind of constraint violation, by the names the penalty function
+    // gives them
+    pub mean_violations: Vec<(String, f64)>,
+    pub feasible_fraction: f64,
+    // Average distance from each individual to the best one
+    pub diversity: f64,
+    // How many children each operator changed, and how many children beat their parent
+    pub crossover_applied: usize,
+    pub mutation_applied: usize,
+    pub repair_applied: usize,
+    pub children_improved: usize,
+    // Individuals replaced by new ones to keep the population diverse
+    pub reinitialized: usize,
+    // Share of the evaluations this generation that were found in the cache
+    pub cache_hit_rate: f64,
+}
+
+impl GenerationStatistics {
+    pub fn from_population<G: Genome>(
+        generation: usize,
+        elapsed_seconds: f64,
+        population: &[G],
+        population_eval: &[f64],
+        population_penalties: &[f64],
+        distance_metric: &dyn DistanceMetric<G>,
+    ) -> GenerationStatistics {
+        let len = population_eval.len() as f64;
+        let evals = || population_eval.iter().copied().flat_map(NotNan::new);
+        let best = evals().enumerate().max_by_key(|&(_, eval)| eval).unwrap();
+        let best_solution = &population[best.0];
+        let total_distance: f64 = population
+            .iter()
+            .map(|solution| distance_metric.distance(solution, best_solution))
+            .sum();
+
+        GenerationStatistics {
+            generation,
+            elapsed_seconds,
+            best_fitness: best.1.into_inner(),
+            mean_fitness: population_eval.iter().sum::<f64>() / len,
+            worst_fitness: evals().min().unwrap().into_inner(),
+            best_penalty: population_penalties
+                .iter()
+                .copied()
+                .flat_map(NotNan::new)
+                .max()
+                .unwrap()
+                .into_inner(),
+            mean_penalty: population_penalties.iter().sum::<f64>() / len,
+            feasible_fraction: population_penalties
+                .iter()
+                .filter(|&&penalty| penalty == 0.)
+                .count() as f64
+                / len,
+            diversity: total_distance / len,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_mean_violations(&mut self, names: Vec<String>, population_violations: &[Vec<f64>]) {
+        let len = population_violations.len() as f64;
+        self.mean_violations = names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let total: f64 = population_violations
+                    .iter()
+                    .filter_map(|violations| violations.get(i))
+                    .sum();
+                (name, total / len)
+            })
+            .collect();
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct StatisticsHistory(pub Vec<GenerationStatistics>);
+
+impl StatisticsHistory {
+    pub fn to_csv(&self) -> String {
+        // Every generation has the same violation columns
+        let violation_columns: String = self.0.first().map_or_else(String::new, |s| {
+            s.mean_violations
+                .iter()
+                .map(|(name, _)| format!("mean_{name},"))
+                .collect()
+        });
+        let mut out = format!(
+            "generation,elapsed_seconds,best_fitness,mean_fitness,worst_fitness,best_penalty,\
+            mean_penalty,{violation_columns}feasible_fraction,diversity,crossover_applied,\
+            mutation_applied,repair_applied,children_improved,reinitialized,cache_hit_rate\n",
+        );
+        for s in &self.0 {
+            let violations: String = s
+                .mean_violations
+                .iter()
+                .map(|(_, mean)| format!("{mean},"))
+                .collect();
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{}{},{},{},{},{},{},{},{}",
+                s.generation,
+                s.elapsed_seconds,
+                s.best_fitness,
+                s.mean_fitness,
+                s.worst_fitness,
+                s.best_penalty,
+                s.mean_penalty,
+                violations,
+                s.feasible_fraction,
+                s.diversity,
+                s.crossover_applied,
+                s.mutation_applied,
+                s.repair_applied,
+                s.children_improved,
+                s.reinitialized,
+                s.cache_hit_rate
+            )
+            .unwrap();
+        }
+        out
+    }
+
+    // Writes JSON if the path ends in .json, CSV otherwise
+    pub fn write<P>(&self, path: P)
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            fs::write(path, serde_json::to_string(&self.0).unwrap()).unwrap();
+        } else {
+            fs::write(path, self.to_csv()).unwrap();
+        }
+    }
+}
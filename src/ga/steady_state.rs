@@ -0,0 +1,167 @@
+use ordered_float::NotNan;
+use rand::Rng;
+use serde::Deserialize;
+
+use super::distance::{distance_matrix, update_distance_matrix};
+use super::individual::count_improved;
+use super::{count_changed, random, GARun, Genome};
+
+// Which individual a new child takes the place of
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum Replacement {
+    Worst,
+    Random,
+    Oldest,
+    // The individual closest to the child, which keeps niches apart
+    MostSimilar,
+}
+
+// Breeds a few children at a time and puts them straight into the population instead of
+// replacing it as a whole. A generation is as many of these steps as it takes to breed a
+// population worth of children, so statistics, termination and checkpoints count the same way as
+// in the generational loop. The replacement takes the place of the survivor selector, which is
+// not used, and the number of children per generation is always the population size.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct SteadyState {
+    // Children per step, 1 or 2
+    #[serde(default = "default_children")]
+    pub children: usize,
+    pub replacement: Replacement,
+    // Only let a child in if it is at least as good as the individual it would replace
+    #[serde(default)]
+    pub only_if_better: bool,
+}
+
+fn default_children() -> usize {
+    2
+}
+
+impl<'a, G: Genome, P: Clone + Send + Sync> GARun<'a, G, P> {
+    pub(super) fn steady_state_step(&mut self, steady_state: SteadyState) {
+        let population_size = self.ga.population.len();
+        let children_per_step = steady_state.children.clamp(1, 2);
+        let mut crossover_applied = 0;
+        let mut mutation_applied = 0;
+        let mut repair_applied = 0;
+        let mut children_improved = 0;
+        // Niching and biased fitness are recomputed every step, as every step changes the
+        // population. Only the distances to the replaced individuals change, so the matrix is
+        // kept up to date instead of measured again.
+        let mut distances = self.ga.selection_uses_distances().then(|| {
+            let individuals: Vec<&G> = self.ga.population.iter().collect();
+            distance_matrix(self.ga.distance_metric, &individuals)
+        });
+
+        for _ in 0..population_size.div_ceil(children_per_step) {
+            let ga = &mut self.ga;
+            let parent_selection_eval = match &distances {
+                Some(distances) => ga.adjust_selection_eval(ga.population_eval.clone(), distances),
+                None => ga.population_eval.clone(),
+            };
+            let (selected_parents, crossed_over, children, repaired) =
+                random::scoped(&mut ga.rng, || {
+                    let selected_parents = ga
+                        .parent_selector
+                        .select_parents_with_penalties(
+                            &ga.population,
+                            &ga.population_eval,
+                            &ga.population_penalites,
+                            2,
+                        )
+                        .unwrap_or_else(|| {
+                            ga.parent_selector.select_parents(
+                                &ga.population,
+                                &parent_selection_eval,
+                                2,
+                            )
+                        });
+                    let mut crossed_over = ga.crossover_system.cross_over(&selected_parents);
+                    crossed_over.truncate(children_per_step);
+                    let children = ga.mutator.mutate(&crossed_over);
+                    let repaired = if let Some(repair) = ga.repair_mechanism {
+                        repair.repair(&children, &ga.instance_description)
+                    } else {
+                        children.clone()
+                    };
+                    (selected_parents, crossed_over, children, repaired)
+                });
+            crossover_applied += count_changed(&selected_parents, &crossed_over);
+            mutation_applied += count_changed(&crossed_over, &children);
+            repair_applied += count_changed(&children, &repaired);
+
+            let (evaluations, computed) = ga.evaluate_cached(&repaired);
+            self.evaluations += computed;
+            let metadata =
+                self.children_metadata(&selected_parents, &crossed_over, &children, &repaired);
+            let children_eval: Vec<f64> = evaluations.iter().map(|e| e.eval()).collect();
+            let children_penalties: Vec<f64> = evaluations.iter().map(|e| e.penalty).collect();
+            self.children_improved_best |= self.ga.hall_of_fame.update(
+                &repaired,
+                &children_eval,
+                &children_penalties,
+                self.generation + 1,
+            );
+            children_improved += count_improved(
+                &children_eval,
+                &metadata,
+                &self.ga.population_eval,
+                &self.ga.population_metadata,
+            );
+            for ((child, evaluation), metadata) in
+                repaired.into_iter().zip(evaluations).zip(metadata)
+            {
+                let victim = self.choose_victim(steady_state.replacement, &child);
+                if steady_state.only_if_better
+                    && evaluation.eval() < self.ga.population_eval[victim]
+                {
+                    continue;
+                }
+                self.put_individual(victim, child, evaluation, metadata);
+                if let Some(distances) = &mut distances {
+                    update_distance_matrix(
+                        distances,
+                        self.ga.distance_metric,
+                        &self.ga.population,
+                        victim,
+                    );
+                }
+            }
+        }
+
+        let generation_statistics = self.ga.statistics.0.last_mut().unwrap();
+        generation_statistics.crossover_applied = crossover_applied;
+        generation_statistics.mutation_applied = mutation_applied;
+        generation_statistics.repair_applied = repair_applied;
+        generation_statistics.children_improved = children_improved;
+    }
+
+    // The best individual is never chosen, so the policies that ignore fitness cannot lose it
+    fn choose_victim(&mut self, replacement: Replacement, child: &G) -> usize {
+        let ga = &mut self.ga;
+        let population_eval = &ga.population_eval;
+        let eval = |i: usize| NotNan::new(population_eval[i]).unwrap();
+        let population_size = ga.population.len();
+        let best = (0..population_size).max_by_key(|&i| eval(i)).unwrap();
+        let candidates: Vec<usize> = (0..population_size)
+            .filter(|&i| i != best || population_size == 1)
+            .collect();
+        match replacement {
+            Replacement::Worst => candidates.into_iter().min_by_key(|&i| eval(i)).unwrap(),
+            Replacement::Random => {
+                let choice =
+                    random::scoped(&mut ga.rng, || random::rng().gen_range(0..candidates.len()));
+                candidates[choice]
+            }
+            Replacement::Oldest => candidates
+                .into_iter()
+                .min_by_key(|&i| ga.population_metadata[i].birth)
+                .unwrap(),
+            Replacement::MostSimilar => candidates
+                .into_iter()
+                .min_by_key(|&i| {
+                    NotNan::new(ga.distance_metric.distance(child, &ga.population[i])).unwrap()
+                })
+                .unwrap(),
+        }
+    }
+}
@@ -1,26 +1,62 @@
-use super::problem::{self, ProblemSolution};
+use super::distance::DistanceMetric;
+use super::penalty_function::deb_key;
+use super::problem::ProblemSolution;
+use super::random;
+use super::Genome;
 use ordered_float::NotNan;
+use rand::{seq::index, Rng};
+use serde::Deserialize;
 
-pub trait SurvivorSelector {
+pub trait SurvivorSelector<G = ProblemSolution>: Sync {
     fn select_survivors(
         &self,
-        parents: &[problem::ProblemSolution],
+        parents: &[G],
         parent_evals: &[f64],
-        children: &[problem::ProblemSolution],
+        children: &[G],
         children_evals: &[f64],
-    ) -> Vec<problem::ProblemSolution>;
+    ) -> Vec<G>;
+
+    // Selectors that need to tell the objective from the penalty override this and return Some.
+    // The evals are those of the fitness and penalty functions, which include the penalty, and
+    // not the ones niching or biased fitness adjusted, as those no longer hold the objective.
+    fn select_survivors_with_penalties(
+        &self,
+        _parents: &[G],
+        _parent_evals: &[f64],
+        _parent_penalties: &[f64],
+        _children: &[G],
+        _children_evals: &[f64],
+        _children_penalties: &[f64],
+    ) -> Option<Vec<G>> {
+        None
+    }
+
+    // Selectors that look at the age of individuals override this and return Some. The others
+    // are called through select_survivors_with_penalties, and then select_survivors. Ages are in
+    // generations, as of the generation the survivors make up.
+    fn select_survivors_with_ages(
+        &self,
+        _parents: &[G],
+        _parent_evals: &[f64],
+        _parent_ages: &[usize],
+        _children: &[G],
+        _children_evals: &[f64],
+        _children_ages: &[usize],
+    ) -> Option<Vec<G>> {
+        None
+    }
 }
 
 pub struct ElitismSelector;
 
-impl SurvivorSelector for ElitismSelector {
+impl<G: Clone> SurvivorSelector<G> for ElitismSelector {
     fn select_survivors(
         &self,
-        parents: &[problem::ProblemSolution],
+        parents: &[G],
         parent_evals: &[f64],
-        children: &[ProblemSolution],
+        children: &[G],
         children_evals: &[f64],
-    ) -> Vec<problem::ProblemSolution> {
+    ) -> Vec<G> {
         let mut out = Vec::from(children);
         //let mut out = children.clone().to_vec();
         let parent_evals_notnan: Vec<_> = parent_evals
@@ -64,62 +100,494 @@ impl TournamentSelector {
     }
 }
 
-impl SurvivorSelector for TournamentSelector {
+impl<G: Genome> SurvivorSelector<G> for TournamentSelector {
     fn select_survivors(
         &self,
-        parents: &[problem::ProblemSolution],
+        parents: &[G],
         parent_evals: &[f64],
-        children: &[ProblemSolution],
+        children: &[G],
         children_evals: &[f64],
-    ) -> Vec<problem::ProblemSolution> {
-        let mut selected_survivors = Vec::with_capacity(children.len());
-
-        // let mut children = children.clone();
-        // let mut children_evals = children_evals.clone().to_vec();
-        // the length should be even
-        for i in 0..(children.len() / 2) {
-            let first_child = &children[i * 2];
-            let second_child = &children[i * 2 + 1];
-            let first_parent = &parents[i * 2];
-            let second_parent = &parents[i * 2 + 1];
-            let unswapped_dist = TournamentSelector::dist(first_child, first_parent)
-                + TournamentSelector::dist(second_child, second_parent);
-            let swapped_dist = TournamentSelector::dist(first_child, second_parent)
-                + TournamentSelector::dist(second_child, first_parent);
-            if swapped_dist < unswapped_dist {
-                if parent_evals[i * 2] > children_evals[i * 2 + 1] {
-                    selected_survivors.push(first_parent.clone());
-                } else {
-                    selected_survivors.push(second_child.clone());
-                }
-                if parent_evals[i * 2 + 1] > children_evals[i * 2] {
-                    selected_survivors.push(second_parent.clone());
-                } else {
-                    selected_survivors.push(first_child.clone());
-                }
-                // children.swap(i*2, i*2 + 1);
-                // children_evals.swap(i*2, i*2 + 1);
+    ) -> Vec<G> {
+        pair_tournament(parents, children, G::distance, |parent, child| {
+            parent_evals[parent] > children_evals[child]
+        })
+    }
+}
+
+// Each pair of children competes with the pair of parents at the same indices, matched so that
+// every child faces the parent it is most similar to. parent_wins(parent, child) decides a duel.
+// Parents beyond the last child survive without a duel, and children beyond the last parent are
+// dropped, so the population keeps its size. A child left without a partner only faces the
+// parent at its own index.
+fn pair_tournament<G: Clone>(
+    parents: &[G],
+    children: &[G],
+    distance: impl Fn(&G, &G) -> f64,
+    mut parent_wins: impl FnMut(usize, usize) -> bool,
+) -> Vec<G> {
+    let mut selected_survivors = Vec::with_capacity(parents.len());
+    let paired = children.len().min(parents.len());
+
+    for i in (0..paired).step_by(2) {
+        if i + 1 == paired {
+            if parent_wins(i, i) {
+                selected_survivors.push(parents[i].clone());
+            } else {
+                selected_survivors.push(children[i].clone());
+            }
+            break;
+        }
+        let first_child = &children[i];
+        let second_child = &children[i + 1];
+        let first_parent = &parents[i];
+        let second_parent = &parents[i + 1];
+        let unswapped_dist =
+            distance(first_child, first_parent) + distance(second_child, second_parent);
+        let swapped_dist =
+            distance(first_child, second_parent) + distance(second_child, first_parent);
+        if swapped_dist < unswapped_dist {
+            if parent_wins(i, i + 1) {
+                selected_survivors.push(first_parent.clone());
+            } else {
+                selected_survivors.push(second_child.clone());
+            }
+            if parent_wins(i + 1, i) {
+                selected_survivors.push(second_parent.clone());
+            } else {
+                selected_survivors.push(first_child.clone());
+            }
+        } else {
+            if parent_wins(i, i) {
+                selected_survivors.push(first_parent.clone());
+            } else {
+                selected_survivors.push(first_child.clone());
+            }
+            if parent_wins(i + 1, i + 1) {
+                selected_survivors.push(second_parent.clone());
             } else {
-                if parent_evals[i * 2] > children_evals[i * 2] {
-                    selected_survivors.push(first_parent.clone());
-                } else {
-                    selected_survivors.push(first_child.clone());
+                selected_survivors.push(second_child.clone());
+            }
+        }
+    }
+    selected_survivors.extend_from_slice(&parents[paired..]);
+    selected_survivors
+}
+
+// Like TournamentSelector, but the duels follow Deb's rules instead of comparing the penalized
+// fitness
+pub struct ConstrainedTournamentSelector;
+
+impl<G: Genome> SurvivorSelector<G> for ConstrainedTournamentSelector {
+    fn select_survivors(
+        &self,
+        parents: &[G],
+        parent_evals: &[f64],
+        children: &[G],
+        children_evals: &[f64],
+    ) -> Vec<G> {
+        let no_penalties = vec![0.; parents.len().max(children.len())];
+        self.select_survivors_with_penalties(
+            parents,
+            parent_evals,
+            &no_penalties,
+            children,
+            children_evals,
+            &no_penalties,
+        )
+        .unwrap()
+    }
+
+    fn select_survivors_with_penalties(
+        &self,
+        parents: &[G],
+        parent_evals: &[f64],
+        parent_penalties: &[f64],
+        children: &[G],
+        children_evals: &[f64],
+        children_penalties: &[f64],
+    ) -> Option<Vec<G>> {
+        Some(pair_tournament(
+            parents,
+            children,
+            G::distance,
+            |parent, child| {
+                deb_key(parent_evals[parent], parent_penalties[parent])
+                    > deb_key(children_evals[child], children_penalties[child])
+            },
+        ))
+    }
+}
+
+// How a selector cuts a pool of candidates down to the population size
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub enum Reduction {
+    // Keep the best
+    Truncation,
+    // Repeatedly keep the best of this many candidates drawn from those not yet kept
+    Tournament(usize),
+}
+
+impl Reduction {
+    // Indices of the candidates to keep
+    fn select(&self, evals: &[f64], amount: usize) -> Vec<usize> {
+        let eval = |i: usize| NotNan::new(evals[i]).unwrap();
+        match *self {
+            Reduction::Truncation => {
+                let mut indices: Vec<usize> = (0..evals.len()).collect();
+                indices.sort_by_key(|&i| std::cmp::Reverse(eval(i)));
+                indices.truncate(amount);
+                indices
+            }
+            Reduction::Tournament(size) => {
+                let mut rng = random::rng();
+                let mut remaining: Vec<usize> = (0..evals.len()).collect();
+                let mut survivors = Vec::with_capacity(amount);
+                while survivors.len() < amount && !remaining.is_empty() {
+                    let drawn = index::sample(&mut rng, remaining.len(), size.min(remaining.len()));
+                    let winner = drawn
+                        .into_iter()
+                        .max_by_key(|&position| eval(remaining[position]))
+                        .unwrap();
+                    survivors.push(remaining.swap_remove(winner));
                 }
-                if parent_evals[i * 2 + 1] > children_evals[i * 2 + 1] {
-                    selected_survivors.push(second_parent.clone());
-                } else {
-                    selected_survivors.push(second_child.clone());
+                survivors
+            }
+        }
+    }
+
+    fn reduce<G: Clone>(&self, pool: Vec<&G>, evals: Vec<f64>, amount: usize) -> Vec<G> {
+        self.select(&evals, amount)
+            .into_iter()
+            .map(|i| pool[i].clone())
+            .collect()
+    }
+}
+
+// (μ+λ): parents and children compete for the μ places, so the best individual is never lost.
+// Any number of children works.
+pub struct PlusSelector {
+    pub reduction: Reduction,
+}
+
+impl<G: Clone> SurvivorSelector<G> for PlusSelector {
+    fn select_survivors(
+        &self,
+        parents: &[G],
+        parent_evals: &[f64],
+        children: &[G],
+        children_evals: &[f64],
+    ) -> Vec<G> {
+        let pool = parents.iter().chain(children).collect();
+        let evals = parent_evals.iter().chain(children_evals).copied().collect();
+        self.reduction.reduce(pool, evals, parents.len())
+    }
+}
+
+// (μ,λ): the parents are discarded and the μ survivors come from the λ children, which need to be
+// at least as many as the parents
+pub struct CommaSelector {
+    pub reduction: Reduction,
+}
+
+impl<G: Clone> SurvivorSelector<G> for CommaSelector {
+    fn select_survivors(
+        &self,
+        parents: &[G],
+        _parent_evals: &[f64],
+        children: &[G],
+        children_evals: &[f64],
+    ) -> Vec<G> {
+        assert!(
+            children.len() >= parents.len(),
+            "(μ,λ) selection needs at least {} children, got {}",
+            parents.len(),
+            children.len()
+        );
+        self.reduction.reduce(
+            children.iter().collect(),
+            children_evals.to_vec(),
+            parents.len(),
+        )
+    }
+}
+
+// TournamentSelector with a choice of distance for matching children to parents
+pub struct DeterministicCrowding<G = ProblemSolution> {
+    pub metric: Box<dyn DistanceMetric<G>>,
+}
+
+impl<G: Clone> SurvivorSelector<G> for DeterministicCrowding<G> {
+    fn select_survivors(
+        &self,
+        parents: &[G],
+        parent_evals: &[f64],
+        children: &[G],
+        children_evals: &[f64],
+    ) -> Vec<G> {
+        pair_tournament(
+            parents,
+            children,
+            |a, b| self.metric.distance(a, b),
+            |parent, child| parent_evals[parent] > children_evals[child],
+        )
+    }
+}
+
+// Like deterministic crowding, but the better of a parent and child only wins with a probability
+// proportional to its share of the two. The evals are treated as negated costs, so this assumes
+// they are not positive, which holds for DefaultFitness and the penalties.
+pub struct ProbabilisticCrowding<G = ProblemSolution> {
+    pub metric: Box<dyn DistanceMetric<G>>,
+}
+
+impl<G: Clone> SurvivorSelector<G> for ProbabilisticCrowding<G> {
+    fn select_survivors(
+        &self,
+        parents: &[G],
+        parent_evals: &[f64],
+        children: &[G],
+        children_evals: &[f64],
+    ) -> Vec<G> {
+        let mut rng = random::rng();
+        pair_tournament(
+            parents,
+            children,
+            |a, b| self.metric.distance(a, b),
+            |parent, child| {
+                let parent_cost = -parent_evals[parent];
+                let child_cost = -children_evals[child];
+                let total = parent_cost + child_cost;
+                if total <= 0. {
+                    return rng.gen_bool(0.5);
                 }
+                // The cheaper one gets the larger share
+                rng.gen_bool((child_cost / total).clamp(0., 1.))
+            },
+        )
+    }
+}
+
+// Restricted tournament replacement: every child is compared with the most similar individual in
+// a random window of the population and takes its place if it is better. Any number of children
+// works.
+pub struct RestrictedTournamentReplacement<G = ProblemSolution> {
+    window_size: usize,
+    metric: Box<dyn DistanceMetric<G>>,
+}
+
+impl<G> RestrictedTournamentReplacement<G> {
+    pub fn new(
+        window_size: usize,
+        metric: Box<dyn DistanceMetric<G>>,
+    ) -> RestrictedTournamentReplacement<G> {
+        assert!(window_size > 0, "the window needs at least one individual");
+        RestrictedTournamentReplacement {
+            window_size,
+            metric,
+        }
+    }
+}
+
+impl<G: Clone> SurvivorSelector<G> for RestrictedTournamentReplacement<G> {
+    fn select_survivors(
+        &self,
+        parents: &[G],
+        parent_evals: &[f64],
+        children: &[G],
+        children_evals: &[f64],
+    ) -> Vec<G> {
+        let mut rng = random::rng();
+        let mut survivors = parents.to_vec();
+        let mut survivor_evals = parent_evals.to_vec();
+        for (child, &child_eval) in children.iter().zip(children_evals) {
+            let window = index::sample(
+                &mut rng,
+                survivors.len(),
+                self.window_size.min(survivors.len()),
+            );
+            let closest = window
+                .into_iter()
+                .min_by_key(|&i| NotNan::new(self.metric.distance(child, &survivors[i])).unwrap())
+                .unwrap();
+            if child_eval > survivor_evals[closest] {
+                survivors[closest] = child.clone();
+                survivor_evals[closest] = child_eval;
+            }
+        }
+        survivors
+    }
+}
+
+// (μ+λ) in which parents older than the lifetime die whatever their eval, apart from the best
+// parent so the best individual is never lost. Copies keep the age of their parent, so they do
+// not get around it. If too few are left, the best of those that died fill up the population.
+pub struct MaxLifetime {
+    pub lifetime: usize,
+    pub reduction: Reduction,
+}
+
+impl<G: Clone> SurvivorSelector<G> for MaxLifetime {
+    // Without ages this is a plain (μ+λ)
+    fn select_survivors(
+        &self,
+        parents: &[G],
+        parent_evals: &[f64],
+        children: &[G],
+        children_evals: &[f64],
+    ) -> Vec<G> {
+        PlusSelector {
+            reduction: self.reduction,
+        }
+        .select_survivors(parents, parent_evals, children, children_evals)
+    }
+
+    fn select_survivors_with_ages(
+        &self,
+        parents: &[G],
+        parent_evals: &[f64],
+        parent_ages: &[usize],
+        children: &[G],
+        children_evals: &[f64],
+        children_ages: &[usize],
+    ) -> Option<Vec<G>> {
+        let population_size = parents.len();
+        let best_parent = (0..parents.len()).max_by_key(|&i| NotNan::new(parent_evals[i]).unwrap());
+        let (mut alive, mut alive_evals) = (Vec::new(), Vec::new());
+        let (mut expired, mut expired_evals) = (Vec::new(), Vec::new());
+        let parents = parents
+            .iter()
+            .zip(parent_evals)
+            .zip(parent_ages)
+            .enumerate();
+        let children = children.iter().zip(children_evals).zip(children_ages);
+        for (i, ((individual, &eval), &age)) in parents {
+            if age <= self.lifetime || Some(i) == best_parent {
+                alive.push(individual);
+                alive_evals.push(eval);
+            } else {
+                expired.push(individual);
+                expired_evals.push(eval);
+            }
+        }
+        for ((individual, &eval), &age) in children {
+            if age <= self.lifetime {
+                alive.push(individual);
+                alive_evals.push(eval);
+            } else {
+                expired.push(individual);
+                expired_evals.push(eval);
+            }
+        }
+        let mut survivors = self.reduction.reduce(alive, alive_evals, population_size);
+        let missing = population_size - survivors.len();
+        survivors.extend(Reduction::Truncation.reduce(expired, expired_evals, missing));
+        Some(survivors)
+    }
+}
+
+// Age-layered population structure after Hornby's ALPS, within one population. The places are
+// split evenly over the layers, and layer l only takes individuals younger than
+// age_gap * (l + 1), apart from the last which takes any age. The layers are filled from the
+// youngest up and those not kept in a layer compete again in the next, so new individuals only
+// compete with each other until they are old enough to meet the established ones.
+pub struct AgeLayered {
+    pub layers: usize,
+    pub age_gap: usize,
+    pub reduction: Reduction,
+}
+
+impl<G: Clone> SurvivorSelector<G> for AgeLayered {
+    // Without ages this is a plain (μ+λ)
+    fn select_survivors(
+        &self,
+        parents: &[G],
+        parent_evals: &[f64],
+        children: &[G],
+        children_evals: &[f64],
+    ) -> Vec<G> {
+        PlusSelector {
+            reduction: self.reduction,
+        }
+        .select_survivors(parents, parent_evals, children, children_evals)
+    }
+
+    fn select_survivors_with_ages(
+        &self,
+        parents: &[G],
+        parent_evals: &[f64],
+        parent_ages: &[usize],
+        children: &[G],
+        children_evals: &[f64],
+        children_ages: &[usize],
+    ) -> Option<Vec<G>> {
+        let population_size = parents.len();
+        let layers = self.layers.max(1);
+        let mut candidates: Vec<(&G, f64, usize)> = parents
+            .iter()
+            .zip(parent_evals)
+            .zip(parent_ages)
+            .chain(children.iter().zip(children_evals).zip(children_ages))
+            .map(|((individual, &eval), &age)| (individual, eval, age))
+            .collect();
+        candidates.sort_by_key(|&(_, _, age)| age);
+
+        let mut survivors = Vec::with_capacity(population_size);
+        let mut pool: Vec<usize> = Vec::new();
+        let mut next = 0;
+        for layer in 0..layers {
+            let age_limit = if layer + 1 == layers {
+                usize::MAX
+            } else {
+                self.age_gap * (layer + 1)
+            };
+            while next < candidates.len() && candidates[next].2 < age_limit {
+                pool.push(next);
+                next += 1;
+            }
+            // The places of this layer and any a younger layer could not fill
+            let places = population_size * (layer + 1) / layers - survivors.len();
+            let pool_evals: Vec<f64> = pool.iter().map(|&c| candidates[c].1).collect();
+            let mut kept = vec![false; pool.len()];
+            for k in self.reduction.select(&pool_evals, places) {
+                kept[k] = true;
+                survivors.push(candidates[pool[k]].0.clone());
             }
+            pool = pool
+                .into_iter()
+                .zip(kept)
+                .filter(|&(_, kept)| !kept)
+                .map(|(c, _)| c)
+                .collect();
         }
-        // for i in 0..children.len() {
-        //     if parent_evals[i] > children_evals[i] {
-        //         selected_survivors.push(parents[i].clone());
-        //     } else {
-        //         selected_survivors.push(children[i].clone());
-        //     }
-        // }
-        // Select the fittest individuals of parent and child with same index
-        selected_survivors
+        Some(survivors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Parents are even numbers and children odd ones, the larger number wins a duel
+    fn survivors(parents: &[i32], children: &[i32]) -> Vec<i32> {
+        pair_tournament(
+            parents,
+            children,
+            |a, b| (a - b).abs() as f64,
+            |parent, child| parents[parent] > children[child],
+        )
+    }
+
+    #[test]
+    fn pair_tournament_keeps_the_population_size() {
+        assert_eq!(
+            survivors(&[10, 20, 30, 40], &[11, 21]),
+            vec![11, 21, 30, 40]
+        );
+        assert_eq!(
+            survivors(&[10, 20, 30, 40], &[11, 21, 31]),
+            vec![11, 21, 31, 40]
+        );
+        assert_eq!(survivors(&[10, 20], &[11, 21, 31, 41]), vec![11, 21]);
+        assert_eq!(survivors(&[20, 10], &[11, 21]), vec![21, 11]);
     }
 }
@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use ordered_float::NotNan;
+
+// What the engine knows about the run when deciding whether to stop
+pub struct RunState<'a> {
+    pub generation: usize,
+    pub elapsed: Duration,
+    pub evaluations: usize,
+    pub best_fitness: f64,
+    pub generations_without_improvement: usize,
+    pub population_eval: &'a [f64],
+}
+
+pub trait TerminationCriterion: Sync {
+    fn should_terminate(&self, state: &RunState) -> bool;
+}
+
+pub struct MaxGenerations(pub usize);
+
+impl TerminationCriterion for MaxGenerations {
+    fn should_terminate(&self, state: &RunState) -> bool {
+        state.generation >= self.0
+    }
+}
+
+pub struct TimeLimit(pub Duration);
+
+impl TerminationCriterion for TimeLimit {
+    fn should_terminate(&self, state: &RunState) -> bool {
+        state.elapsed >= self.0
+    }
+}
+
+pub struct MaxEvaluations(pub usize);
+
+impl TerminationCriterion for MaxEvaluations {
+    fn should_terminate(&self, state: &RunState) -> bool {
+        state.evaluations >= self.0
+    }
+}
+
+// Stops after the given number of generations without the best fitness improving
+pub struct Stagnation(pub usize);
+
+impl TerminationCriterion for Stagnation {
+    fn should_terminate(&self, state: &RunState) -> bool {
+        state.generations_without_improvement >= self.0
+    }
+}
+
+// Stops once the best individual has a total duration of at most the target. Since the fitness is
+// the negated duration plus the (negative) penalty, penalties count towards the duration.
+pub struct TargetObjective(pub f64);
+
+impl TerminationCriterion for TargetObjective {
+    fn should_terminate(&self, state: &RunState) -> bool {
+        -state.best_fitness <= self.0
+    }
+}
+
+// Stops when the fitnesses of the population are all within the tolerance of each other
+pub struct PopulationConvergence(pub f64);
+
+impl TerminationCriterion for PopulationConvergence {
+    fn should_terminate(&self, state: &RunState) -> bool {
+        let evals = || state.population_eval.iter().copied().flat_map(NotNan::new);
+        match (evals().min(), evals().max()) {
+            (Some(min), Some(max)) => max.into_inner() - min.into_inner() <= self.0,
+            _ => false,
+        }
+    }
+}
+
+pub struct AnyOf(pub Vec<Box<dyn TerminationCriterion>>);
+
+impl TerminationCriterion for AnyOf {
+    fn should_terminate(&self, state: &RunState) -> bool {
+        self.0
+            .iter()
+            .any(|criterion| criterion.should_terminate(state))
+    }
+}
+
+pub struct AllOf(pub Vec<Box<dyn TerminationCriterion>>);
+
+impl TerminationCriterion for AllOf {
+    fn should_terminate(&self, state: &RunState) -> bool {
+        self.0
+            .iter()
+            .all(|criterion| criterion.should_terminate(state))
+    }
+}
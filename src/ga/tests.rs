@@ -0,0 +1,178 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+// A knapsack problem, to check that nothing in the engine depends on the VRP types
+#[derive(Clone)]
+struct Knapsack {
+    values: Vec<f64>,
+    weights: Vec<f64>,
+    capacity: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+struct Items(Vec<bool>);
+
+impl Genome for Items {
+    fn distance(&self, other: &Self) -> f64 {
+        self.0.iter().zip(&other.0).filter(|(a, b)| a != b).count() as f64
+    }
+}
+
+fn total(items: &Items, amounts: &[f64]) -> f64 {
+    items
+        .0
+        .iter()
+        .zip(amounts)
+        .filter(|(&packed, _)| packed)
+        .map(|(_, amount)| amount)
+        .sum()
+}
+
+struct RandomItems {
+    population: usize,
+    items: usize,
+}
+
+impl initializer::PopulationInitializer<Items> for RandomItems {
+    fn population_size(&self) -> usize {
+        self.population
+    }
+
+    fn initialize_individuals(&self, amount: usize) -> Vec<Items> {
+        let mut rng = random::rng();
+        (0..amount)
+            .map(|_| Items((0..self.items).map(|_| rng.gen_bool(0.5)).collect()))
+            .collect()
+    }
+}
+
+struct Value;
+
+impl fitness_function::FitnessFunction<Items, Knapsack> for Value {
+    fn get_fitness(&self, solution: &Items, instance: &Knapsack) -> f64 {
+        total(solution, &instance.values)
+    }
+}
+
+struct Overweight;
+
+impl penalty_function::PenaltyFunction<Items, Knapsack> for Overweight {
+    fn get_penalty(&self, solution: &Items, instance: &Knapsack) -> f64 {
+        (instance.capacity - total(solution, &instance.weights)).min(0.) * 10.
+    }
+
+    fn violation_names(&self) -> Vec<String> {
+        vec![String::from("overweight")]
+    }
+
+    fn get_penalty_and_violations(&self, solution: &Items, instance: &Knapsack) -> (f64, Vec<f64>) {
+        let penalty = self.get_penalty(solution, instance);
+        (penalty, vec![penalty])
+    }
+}
+
+struct OnePoint;
+
+impl crossover::ParentCrossoverSystem<Items> for OnePoint {
+    fn cross_over(&self, parents: &[Items]) -> Vec<Items> {
+        let mut rng = random::rng();
+        let mut children = Vec::with_capacity(parents.len());
+        for pair in parents.chunks(2) {
+            if let [a, b] = pair {
+                let point = rng.gen_range(0..a.0.len());
+                children.push(Items([&a.0[..point], &b.0[point..]].concat()));
+                children.push(Items([&b.0[..point], &a.0[point..]].concat()));
+            } else {
+                children.extend_from_slice(pair);
+            }
+        }
+        children
+    }
+}
+
+struct BitFlip;
+
+impl mutator::Mutator<Items> for BitFlip {
+    fn mutate(&self, children: &[Items]) -> Vec<Items> {
+        let mut rng = random::rng();
+        children
+            .iter()
+            .map(|child| {
+                Items(
+                    child
+                        .0
+                        .iter()
+                        .map(|&bit| bit ^ rng.gen_bool(0.05))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn runs_on_a_problem_other_than_the_vrp() {
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let knapsack = Knapsack {
+        values: (0..30).map(|_| rng.gen_range(1.0..10.)).collect(),
+        weights: (0..30).map(|_| rng.gen_range(1.0..10.)).collect(),
+        capacity: 60.,
+    };
+    let initializer = RandomItems {
+        population: 20,
+        items: 30,
+    };
+    let parent_selector = parent_selector::TournamentParentSelector::new(2);
+    let survivor_selector = survivor_selection::PlusSelector {
+        reduction: survivor_selection::Reduction::Truncation,
+    };
+    let termination = termination::MaxGenerations(40);
+    let ga = GA {
+        instance_description: knapsack.clone(),
+        population_initializer: &initializer,
+        fitness_function: &Value,
+        penalty_function: Some(&Overweight),
+        parent_selector: &parent_selector,
+        crossover_system: &OnePoint,
+        mutator: &BitFlip,
+        repair_mechanism: None,
+        survivor_selector: &survivor_selector,
+        termination: &termination,
+        observers: Vec::new(),
+        distance_metric: &distance::GenomeDistance,
+        offspring: None,
+        steady_state: None,
+        diversity: None,
+        biased_fitness: None,
+        niching: None,
+        population: Vec::new(),
+        population_eval: Vec::new(),
+        population_penalites: Vec::new(),
+        population_violations: Vec::new(),
+        population_metadata: Vec::new(),
+        children: Vec::new(),
+        children_eval: Vec::new(),
+        children_penalties: Vec::new(),
+        statistics: Default::default(),
+        hall_of_fame: archive::HallOfFame::new(5),
+        cache: cache::EvaluationCache::new(100),
+        rng,
+        resume_from: None,
+    };
+
+    let result = ga.start();
+
+    let statistics = &result.statistics.0;
+    assert_eq!(statistics.len(), 41);
+    assert_eq!(statistics[0].mean_violations[0].0, "overweight");
+    assert!(result.statistics.to_csv().starts_with(
+        "generation,elapsed_seconds,best_fitness,mean_fitness,worst_fitness,best_penalty,\
+        mean_penalty,mean_overweight,feasible_fraction"
+    ));
+    let best = result.hall_of_fame.best_feasible.as_ref().unwrap();
+    assert!(total(&best.solution, &knapsack.weights) <= knapsack.capacity);
+    assert_eq!(best.fitness, total(&best.solution, &knapsack.values));
+}
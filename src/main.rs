@@ -5,29 +5,49 @@ use std::{
     env,
     fmt::{format, Pointer, Write},
     fs::{self, File},
+    path::PathBuf,
     thread::{self, Thread},
 };
 
-use ga::problem::{ProblemSolution, SolutionField};
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use ga::problem::SolutionField;
+use ordered_float::NotNan;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 
+use crate::config::{NichingConfig, RunConfig};
 use crate::ga::problem::{InstanceDescription, Patient};
 
-
+mod config;
 mod ga;
 
 fn main() {
     //env::set_var("RUST_BACKTRACE", "1");
-    let res = InstanceDescription::from_file("./test/test_2.json");
-
-    let patient_amount = res.patients.len();
-    let nurses = res.nbr_nurses;
-
-    let i = ga::initializer::DefaultInitializer {
-        initial_population: 100,
-        patient_amount,
-        nurses,
+    // Either `<run config>` or `resume <checkpoint directory>`
+    let args: Vec<String> = env::args().collect();
+    let (config_path, resume_directory) = if args.get(1).map(String::as_str) == Some("resume") {
+        let directory = PathBuf::from(&args[2]);
+        (directory.join("run_config.json"), Some(directory))
+    } else {
+        let path = args
+            .get(1)
+            .cloned()
+            .unwrap_or_else(|| String::from("run_config.json"));
+        (PathBuf::from(path), None)
     };
+    let config = RunConfig::from_file(&config_path);
+    let checkpoint_directory = resume_directory.clone().or_else(|| {
+        config
+            .checkpoint
+            .as_ref()
+            .map(|checkpoint| PathBuf::from(&checkpoint.directory))
+    });
+    if let (Some(directory), None) = (&checkpoint_directory, &resume_directory) {
+        fs::create_dir_all(directory).unwrap();
+        fs::copy(&config_path, directory.join("run_config.json")).unwrap();
+    }
+    let res = InstanceDescription::from_file(&config.instance);
+
+    let i = config.build_initializer(&res);
 
     let ff = ga::fitness_function::DefaultFitness {};
     let pf = ga::penalty_function::DefaultPenalty {
@@ -35,9 +55,6 @@ fn main() {
         missed_window_multiplier: 50.,
         overcapacity_multiplier: 1000.,
     };
-    let co = ga::crossover::DefaultCrossover {
-        crossover_rate: 0.3,
-    };
     // let co = ga::crossover::EdgeCrossover {
     //     crossover_rate: 0.3,
     // };
@@ -45,92 +62,143 @@ fn main() {
     //let mt = ga::mutator::DefaultMutator { mutation_rate: 0.03 };
     // let mt = ga::mutator::InsertionMutator {mutation_rate: 0.01};
     // let mt = ga::mutator::NeighbourSwapAndInsertMutator {swap_probability: 0.01, insertion_probability: 0.01};
-    let mt = ga::mutator::SwapAndInsertMutator {
-        swap_rate: 0.0075,
-        insert_rate: 0.01,
-        problem_instance: res.clone(),
-    };
-    // let mt = ga::mutator::SwapAndInsertMutator {swap_rate: 0.0075, insert_rate: 0.009, problem_instance: res.clone()};
     // let mt = ga::mutator::SmartInsertionMutator {mutation_rate: 0.01, problem_instance: res.clone()};
+    // let mt = ga::mutator::RelocateMutator {mutation_rate: 0.1, time_warp_weight: 50., overload_weight: 1000., problem_instance: res.clone()};
+    let operators: Vec<_> = (0..config.runs)
+        .map(|island| {
+            let operator = config.islands.operators(island);
+            let co = ga::crossover::DefaultCrossover {
+                crossover_rate: operator.crossover_rate,
+            };
+            let mt = ga::mutator::SwapAndInsertMutator {
+                swap_rate: operator.swap_rate,
+                insert_rate: operator.insert_rate,
+                problem_instance: res.clone(),
+            };
+            (co, mt)
+        })
+        .collect();
 
     // let ss = ga::survivor_selection::ElitismSelector;
     let ss = ga::survivor_selection::TournamentSelector;
+    // let ss = ga::survivor_selection::ConstrainedTournamentSelector;
+    // let ss = ga::survivor_selection::PlusSelector { reduction: ga::survivor_selection::Reduction::Truncation };
+    // let ss = ga::survivor_selection::CommaSelector { reduction: ga::survivor_selection::Reduction::Tournament(2) };
+    // let ss = ga::survivor_selection::DeterministicCrowding { metric: Box::new(ga::distance::BrokenPairs) };
+    // let ss = ga::survivor_selection::ProbabilisticCrowding { metric: Box::new(ga::distance::BrokenPairs) };
+    // let ss = ga::survivor_selection::RestrictedTournamentReplacement::new(20, Box::new(ga::distance::RouteAssignment));
+    // let ss = ga::survivor_selection::MaxLifetime { lifetime: 50, reduction: ga::survivor_selection::Reduction::Truncation };
+    // let ss = ga::survivor_selection::AgeLayered { layers: 4, age_gap: 10, reduction: ga::survivor_selection::Reduction::Truncation };
 
     // let ps = ga::parent_selector::DefaultParentSelector {};
+    // let ps = ga::parent_selector::TournamentParentSelector::new(2);
+    // let ps = ga::parent_selector::LinearRankSelector::new(1.5);
+    // let ps = ga::parent_selector::ExponentialRankSelector { base: 0.98 };
+    // let ps = ga::parent_selector::StochasticUniversalSampling;
+    // let ps = ga::parent_selector::BoltzmannSelector { temperature: 100. };
+    // let ps = ga::parent_selector::ConstrainedTournamentParentSelector::new(2);
     let ps = ga::parent_selector::IdentityParentSelector;
 
     let rm = ga::repair_mechanism::DefaultRepair;
 
+    let dm = ga::distance::BrokenPairs;
+    // let dm = ga::distance::RouteAssignment;
+    // let dm = ga::distance::EditDistance;
+    // let dm = ga::distance::Hamming;
+
+    let nc = config.niching.as_ref().map(NichingConfig::build);
+    let tc = config.termination.build();
+    let console = ga::observer::ConsoleObserver { interval: 3000 };
+
     let mut string = String::new();
     string.push_str(&format!("Nurse capactity: {}\n", &res.capacity_nurse));
     string.push_str(&format!("Depot return time: {}\n", &res.depot.return_time));
 
-    let thing = ga::GA {
-        instance_description: res.clone(),
-        population_initializer: &i,
-        fitness_function: &ff,
-        penalty_function: Some(&pf),
-        parent_selector: &ps,
-        crossover_system: &co,
-        mutator: &mt,
-        repair_mechanism: Some(&rm),
-        survivor_selector: &ss,
-        population: Vec::new(),
-        population_eval: Vec::new(),
-        population_penalites: Vec::new(),
-        children: Vec::new(),
-        children_eval: Vec::new(),
+    let seed_rng = |offset: u64| match config.seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed + offset),
+        None => ChaCha8Rng::from_entropy(),
     };
-
-    let cores = 6;
-    let mut pars = Vec::new();
-    for _ in 0..cores {
-        pars.push(thing.clone());
-    }
-
-    let mut runs: Vec<_> = (0..cores)
-        .into_par_iter()
-        .map(|_| {
-            let thing = ga::GA {
-                instance_description: res.clone(),
-                population_initializer: &i,
-                fitness_function: &ff,
-                penalty_function: Some(&pf),
-                parent_selector: &ps,
-                crossover_system: &co,
-                mutator: &mt,
-                repair_mechanism: Some(&rm),
-                survivor_selector: &ss,
-                population: Vec::new(),
-                population_eval: Vec::new(),
-                population_penalites: Vec::new(),
-                children: Vec::new(),
-                children_eval: Vec::new(),
-            };
-            thing.start(240000, true, 3000)
+    let islands = operators
+        .iter()
+        .enumerate()
+        .map(|(island, (co, mt))| ga::GA {
+            instance_description: res.clone(),
+            population_initializer: &i,
+            fitness_function: &ff,
+            penalty_function: Some(&pf),
+            parent_selector: &ps,
+            crossover_system: co,
+            mutator: mt,
+            repair_mechanism: Some(&rm),
+            survivor_selector: &ss,
+            termination: tc.as_ref(),
+            observers: vec![&console],
+            distance_metric: &dm,
+            offspring: config.offspring,
+            steady_state: config.steady_state,
+            diversity: config.diversity,
+            biased_fitness: config.biased_fitness,
+            niching: nc.as_deref(),
+            population: Vec::new(),
+            population_eval: Vec::new(),
+            population_penalites: Vec::new(),
+            population_violations: Vec::new(),
+            population_metadata: Vec::new(),
+            children: Vec::new(),
+            children_eval: Vec::new(),
+            children_penalties: Vec::new(),
+            statistics: Default::default(),
+            hall_of_fame: ga::archive::HallOfFame::new(10),
+            cache: ga::cache::EvaluationCache::new(config.evaluation_cache),
+            rng: seed_rng(island as u64),
+            resume_from: None,
         })
         .collect();
-    let mut population: Vec<ProblemSolution> = Vec::new();
-    let mut eval: Vec<f64> = Vec::new();
-    for (p, e, _) in runs {
-        population.append(&mut p.clone());
-        eval.append(&mut e.clone())
+    let checkpoint = checkpoint_directory
+        .as_ref()
+        .map(|directory| ga::island::IslandCheckpoint {
+            interval: config.checkpoint.as_ref().map_or(1000, |c| c.interval),
+            path: directory.join("islands.json"),
+        });
+    // A run that had not reached its first checkpoint starts over
+    let resume_from = resume_directory
+        .as_ref()
+        .map(|directory| directory.join("islands.json"))
+        .filter(|path| path.exists())
+        .map(ga::island::IslandState::from_file);
+    let model = ga::island::IslandModel {
+        islands,
+        migration_interval: config.islands.migration_interval,
+        migrants: config.islands.migrants,
+        topology: config.islands.topology,
+        migrant_selection: config.islands.migrant_selection,
+        replacement: config.islands.replacement,
+        rng: seed_rng(config.runs as u64),
+        checkpoint,
+        resume_from,
+    };
+    let runs = model.start();
+    for (run, result) in runs.iter().enumerate() {
+        if let Some(path) = &config.statistics_output {
+            result
+                .statistics
+                .write(path.replace("{run}", &run.to_string()));
+        }
     }
 
     //let (population, eval, _) = thing.start(300000, true, 1000);
     {
-        let best = eval
+        // Prefer the best feasible solution of any run, fall back to the best infeasible one
+        let best_feasible = runs
             .iter()
-            .enumerate()
-            .fold((0, eval[0]), |(idx_max, val_max), (idx, val)| {
-                if &val_max > val {
-                    (idx_max, val_max)
-                } else {
-                    (idx, *val)
-                }
-            });
+            .filter_map(|result| result.hall_of_fame.best_feasible.as_ref())
+            .max_by_key(|archived| NotNan::new(archived.fitness).unwrap());
+        let best = runs
+            .iter()
+            .filter_map(|result| result.hall_of_fame.best())
+            .max_by_key(|archived| NotNan::new(archived.fitness).unwrap());
 
-        let bestest = population[best.0].clone();
+        let bestest = best_feasible.or(best).unwrap().solution.clone();
 
         let mut current_time = 0.;
         let mut travel_duration: f64 = 0.;